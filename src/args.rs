@@ -1,9 +1,13 @@
-use crate::{structs::Config, utils::{parse_request, adjust_body}};
+use crate::{graphql::graphql_configs, structs::{Config, Location}, values::COMMON_VALUES, import::{from_burp, from_curl, from_har, from_openapi, Filter}, utils::{
+    add_cookie_injection, adjust_multipart_body, json_body_configs, multipart_parameter_template, parse_request,
+    parse_url, adjust_body, read_lines, PRESETS, set_multipart_content_type, xml_parameter_template
+}};
+use regex::Regex;
 use clap::{crate_version, App, AppSettings, Arg};
-use std::{collections::HashMap, fs, time::Duration, io::{self, Write}};
-use url::Url;
+use std::{collections::HashMap, fs, sync::Arc, time::Duration, io::{self, BufRead, Write}};
+use tokio::sync::Semaphore;
 
-pub fn get_config() -> (Config, usize) {
+pub fn get_config() -> Vec<Config> {
 
     let app = App::new("x8")
         .setting(AppSettings::ArgRequiredElseHelp)
@@ -17,13 +21,60 @@ pub fn get_config() -> (Config, usize) {
             .takes_value(true)
             .conflicts_with("request")
         )
+        .arg(Arg::with_name("urls")
+            .long("urls")
+            .help("The file with target urls, one per line. Use - to read urls from stdin")
+            .takes_value(true)
+            .conflicts_with("url")
+        )
         .arg(Arg::with_name("request")
             .short("r")
             .long("request")
-            .help("The file with the raw http request")
+            .help("The file(s) with the raw http request")
+            .takes_value(true)
+            .min_values(1)
+            .conflicts_with("url")
+        )
+        .arg(Arg::with_name("curl")
+            .long("curl")
+            .help("The curl command to take the request from. Example: --curl \"$(pbpaste)\"")
+            .takes_value(true)
+            .conflicts_with_all(&["url", "request"])
+        )
+        .arg(Arg::with_name("burp")
+            .long("burp")
+            .help("Burp Suite xml export (Save items) to take the requests from")
+            .takes_value(true)
+            .conflicts_with("url")
+        )
+        .arg(Arg::with_name("har")
+            .long("har")
+            .help("The har file to take the requests from")
             .takes_value(true)
             .conflicts_with("url")
         )
+        .arg(Arg::with_name("openapi")
+            .long("openapi")
+            .help("OpenAPI 3 or Swagger 2 spec (json or yaml). Every operation is scanned for undocumented parameters")
+            .takes_value(true)
+            .conflicts_with("url")
+        )
+        .arg(Arg::with_name("openapi-base")
+            .long("openapi-base")
+            .help("The base url for the operations from --openapi. Taken from the spec by default")
+            .takes_value(true)
+            .requires("openapi")
+        )
+        .arg(Arg::with_name("filter-host")
+            .long("filter-host")
+            .help("Import only requests with hosts that match this regex. Example: --filter-host '(^|\\.)example\\.com$'")
+            .takes_value(true)
+        )
+        .arg(Arg::with_name("filter-path")
+            .long("filter-path")
+            .help("Import only requests with paths that match this regex. Example: --filter-path '^/api/'")
+            .takes_value(true)
+        )
         .arg(Arg::with_name("proto")
             .long("proto")
             .help("Protocol to use with request file (default is \"https\")")
@@ -58,7 +109,7 @@ pub fn get_config() -> (Config, usize) {
             Arg::with_name("body-type")
                 .short("t")
                 .long("body-type")
-                .help("Available: urlencode, json\nCan be detected automatically if --body is specified (default is \"urlencode\")")
+                .help("Available: urlencode, json, xml, multipart\nCan be detected automatically if --body is specified (default is \"urlencode\")")
                 .value_name("body type")
         )
         .arg(
@@ -86,10 +137,17 @@ pub fn get_config() -> (Config, usize) {
             Arg::with_name("output-format")
                 .short("O")
                 .long("output-format")
-                .help("standart, json, url, request")
+                .help("standart, json, url, request, sarif, junit, html")
                 .default_value("standart")
                 .takes_value(true)
         )
+        .arg(
+            Arg::with_name("expected-parameters")
+                .long("expected-parameters")
+                .help("Parameters that are allowed to be found.\nOther found parameters fail the junit test cases")
+                .takes_value(true)
+                .min_values(1)
+        )
         .arg(
             Arg::with_name("append")
                 .long("append")
@@ -114,7 +172,36 @@ pub fn get_config() -> (Config, usize) {
         .arg(
             Arg::with_name("as-body")
                 .long("as-body")
-                .help("Send parameters via body.\nBuilt in body types that can be detected automatically: json, urlencode")
+                .help("Send parameters via body.\nBuilt in body types that can be detected automatically: json, urlencode, xml")
+        )
+        .arg(
+            Arg::with_name("nested")
+                .long("nested")
+                .help("Search for parameters within every object of the json body.\nFound parameters are reported with their paths like user.settings.debug")
+                .requires("as-body")
+        )
+        .arg(
+            Arg::with_name("typed")
+                .long("typed")
+                .help("Check parameters with number, boolean, array and object values as well.\nWorks with json bodies only")
+                .requires("as-body")
+        )
+        .arg(
+            Arg::with_name("graphql")
+                .long("graphql")
+                .value_name("query")
+                .help("Switch to GraphQL mode. Arguments and selection fields of --graphql-field are searched for separately.\nExample: --graphql 'query { user(id: 1) { name } }' --graphql-field user")
+                .takes_value(true)
+                .requires("graphql-field")
+                .conflicts_with_all(&["headers-discovery", "cookies", "nested", "typed", "body"])
+        )
+        .arg(
+            Arg::with_name("graphql-field")
+                .long("graphql-field")
+                .value_name("field")
+                .help("The field from the GraphQL query to search for arguments and selection fields of")
+                .takes_value(true)
+                .requires("graphql")
         )
         .arg(
             Arg::with_name("headers-discovery")
@@ -123,6 +210,50 @@ pub fn get_config() -> (Config, usize) {
                 .conflicts_with("as-body")
                 .conflicts_with("param-template")
         )
+        .arg(
+            Arg::with_name("hpp")
+                .long("hpp")
+                .help("Check how duplicates of the found parameters are treated: p=a&p=b, p[]=a&p[]=b and copies within the query and the body.\nWorks with query and body parameters")
+        )
+        .arg(
+            Arg::with_name("special-chars")
+                .long("special-chars")
+                .help("Check how the reflected parameters return special chars: < > \" ' ` { \\\nEvery char is reported as raw, escaped, html_encoded, url_encoded, replaced or stripped")
+        )
+        .arg(
+            Arg::with_name("timing")
+                .long("timing")
+                .help("Report parameters that make responses significantly slower, e.g. cache bypasses or debug queries.\nThe usual response time is learned from the learning requests")
+        )
+        .arg(
+            Arg::with_name("enumerate")
+                .long("enumerate")
+                .value_name("values")
+                .help("Check the found parameters with common values (booleans, numbers, roles, formats, debug levels) and the specified ones.\nValues that produce distinct responses are reported. Example: --enumerate yaml v2")
+                .takes_value(true)
+                .min_values(0)
+        )
+        .arg(
+            Arg::with_name("mixed")
+                .long("mixed")
+                .help("Scan query, body, cookies and headers of the same target one by one.\nFound parameters are reported with every location they were accepted in")
+                .conflicts_with_all(&["as-body", "headers-discovery", "cookies", "graphql", "path-injection"])
+        )
+        .arg(
+            Arg::with_name("path-injection")
+                .long("path-injection")
+                .value_name("mode")
+                .help("Send parameters within the path: matrix - /api;k=v, segments - /api/k/v.\nEvery key and value is percent-encoded as a separate segment")
+                .takes_value(true)
+                .possible_values(&["matrix", "segments"])
+                .conflicts_with_all(&["as-body", "headers-discovery", "cookies", "graphql"])
+        )
+        .arg(
+            Arg::with_name("cookies")
+                .long("cookies")
+                .help("Switch to cookie discovery mode.\nParameters are added to the existing Cookie header")
+                .conflicts_with_all(&["as-body", "headers-discovery"])
+        )
         .arg(
             Arg::with_name("force")
                 .long("force")
@@ -164,6 +295,23 @@ pub fn get_config() -> (Config, usize) {
                 .long("replay-proxy")
                 .help("Request target with every found parameter via replay proxy at the end.")
         )
+        .arg(
+            Arg::with_name("preset")
+                .long("preset")
+                .value_name("preset")
+                .help("Check words as nested keys: php-array - word[], php-object and rails - parent[word], spring and aspnet - parent.word.\nParents are taken from --parents, the request and the page")
+                .takes_value(true)
+                .possible_values(&PRESETS)
+                .conflicts_with_all(&["headers-discovery", "graphql"])
+        )
+        .arg(
+            Arg::with_name("parents")
+                .long("parents")
+                .help("Parents to discover the nested keys of. Example: --preset rails --parents user account")
+                .takes_value(true)
+                .min_values(1)
+                .requires("preset")
+        )
         .arg(
             Arg::with_name("custom-parameters")
                 .long("custom-parameters")
@@ -233,6 +381,14 @@ pub fn get_config() -> (Config, usize) {
                 .default_value("9")
                 .takes_value(true)
         )
+        .arg(
+            Arg::with_name("mask")
+                .long("mask")
+                .value_name("regex")
+                .help("Replace dynamic parts of responses before comparing them. The first group is replaced if there is one.\nExample: --mask 'csrf\" value=\"([^\"]+)'\nMasks for values that change between identical requests are learned automatically")
+                .takes_value(true)
+                .min_values(1)
+        )
         .arg(
             Arg::with_name("max")
                 .short("m")
@@ -243,7 +399,15 @@ pub fn get_config() -> (Config, usize) {
         .arg(
             Arg::with_name("concurrency")
                 .short("c")
-                .help("The number of concurrent requests")
+                .help("The number of concurrent requests. The limit is shared by the targets that are scanned at the same time")
+                .default_value("1")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("workers")
+                .short("W")
+                .long("workers")
+                .help("The number of targets scanned at the same time")
                 .default_value("1")
                 .takes_value(true)
         )
@@ -260,7 +424,9 @@ pub fn get_config() -> (Config, usize) {
 
     let args = app.clone().get_matches();
 
-    if args.value_of("url").is_none() && args.value_of("request").is_none() {
+    if args.value_of("url").is_none() && args.value_of("urls").is_none() && args.value_of("request").is_none()
+        && args.value_of("burp").is_none() && args.value_of("har").is_none() && args.value_of("openapi").is_none()
+        && args.value_of("curl").is_none() {
         writeln!(io::stderr(), "A target was not provided").ok();
         std::process::exit(1);
     }
@@ -282,8 +448,30 @@ pub fn get_config() -> (Config, usize) {
     let value_size = parse_int(&args, "value_size");
     let learn_requests_count = parse_int(&args, "learn_requests_count");
     let concurrency = parse_int(&args, "concurrency");
+    let workers = parse_int(&args, "workers");
+
+    //nothing would be sent with 0 requests or targets at a time
+    for (name, value) in [("concurrency", concurrency), ("workers", workers)] {
+        if value == 0 {
+            writeln!(io::stderr(), "'{}' value should be greater than 0", name).ok();
+            std::process::exit(1);
+        }
+    }
     let verbose = parse_int(&args, "verbose");
 
+    let mut masks: Vec<Regex> = Vec::new();
+    if let Some(val) = args.values_of("mask") {
+        for mask in val {
+            match Regex::new(mask) {
+                Ok(val) => masks.push(val),
+                Err(err) => {
+                    writeln!(io::stderr(), "Unable to parse '{}' mask: {}", mask, err).ok();
+                    std::process::exit(1);
+                }
+            }
+        }
+    }
+
     let mut headers: HashMap<String, String> = HashMap::new();
     let mut within_headers: bool = false;
     if let Some(val) = args.values_of("headers") {
@@ -315,17 +503,10 @@ pub fn get_config() -> (Config, usize) {
         }
     };
 
-    //default value is used only in case a request file is used. Then it gets overwrited in parse_request()
-    let url = match Url::parse(args.value_of("url").unwrap_or("https://4rt.one")) {
-        Ok(val) => val,
-        Err(err) => {
-            writeln!(io::stderr(), "Unable to parse target url: {}", err).ok();
-            std::process::exit(1);
-        }
-    };
-
-    let host = url.host_str().unwrap();
-    let mut path = url[url::Position::BeforePath..].to_string();
+    if args.is_present("cookies") {
+        within_headers = true;
+        add_cookie_injection(&mut headers);
+    }
 
     let body = match args.is_present("keep-newlines") {
         true => args.value_of("body").unwrap_or("").replace("\\n", "\n").replace("\\r", "\r"),
@@ -335,6 +516,9 @@ pub fn get_config() -> (Config, usize) {
     //check whether it is possible to automatically fix body type
     //- at the end means "specified automatically"
     let body_type = if args.value_of("body-type").is_none() && args.value_of("parameter_template").unwrap().is_empty()
+        && headers.contains_key("Content-Type") && headers["Content-Type"].contains("multipart/form-data") {
+        String::from("multipart-")
+    } else if args.value_of("body-type").is_none() && args.value_of("parameter_template").unwrap().is_empty()
         && (
             (
                 !body.is_empty() && body.starts_with('{')
@@ -344,11 +528,30 @@ pub fn get_config() -> (Config, usize) {
             )
         ) {
         String::from("json-")
+    } else if args.value_of("body-type").is_none() && args.value_of("parameter_template").unwrap().is_empty()
+        && (
+            body.trim_start().starts_with('<')
+            || (
+                headers.contains_key("Content-Type") && headers["Content-Type"].contains("xml")
+            )
+        ) {
+        String::from("xml-")
     } else {
         args.value_of("body-type").unwrap_or("urlencode-").to_string()
     };
 
-    let body = if !body.contains("%s") && args.is_present("as-body") {
+    let mut multipart_template = String::new();
+
+    let body = if body_type.contains("multipart") && args.is_present("as-body") {
+        let boundary = set_multipart_content_type(&mut headers);
+        multipart_template = multipart_parameter_template(&boundary);
+
+        if body.contains("%s") {
+            body
+        } else {
+            adjust_multipart_body(&body, &boundary)
+        }
+    } else if !body.contains("%s") && args.is_present("as-body") {
         adjust_body(&body, &body_type)
     } else {
         body
@@ -383,6 +586,11 @@ pub fn get_config() -> (Config, usize) {
                 String::from("Content-Type"),
                 String::from("application/json"),
             );
+        } else if body_type.contains("xml") {
+            headers.insert(
+                String::from("Content-Type"),
+                String::from("application/xml"),
+            );
         } else {
             headers.insert(
                 String::from("Content-Type"),
@@ -391,35 +599,25 @@ pub fn get_config() -> (Config, usize) {
         }
     }
 
-    let mut url = url.to_string();
-
-    if !args.is_present("as-body") && !within_headers && !args.is_present("headers-discovery") && url.contains('?') && url.contains('=') && !url.contains("%s") {
-        if args.is_present("encode") {
-            url.push_str("%26%s");
-            path.push_str("%26%s");
-        } else {
-            url.push_str("&%s");
-            path.push_str("&%s");
-        }
-    } else if !args.is_present("as-body") && !within_headers &&!args.is_present("headers-discovery") && !url.contains("%s") {
-        if args.is_present("encode") {
-            url.push_str("%3f%s");
-            path.push_str("%3f%s");
-        } else {
-            url.push_str("?%s");
-            path.push_str("?%s");
-        }
-    }
-
     let parameter_template = match args.is_present("keep-newlines") {
         true => args.value_of("parameter_template").unwrap_or("").replace("\\n", "\n").replace("\\r", "\r"),
         false => args.value_of("parameter_template").unwrap_or("").to_string()
     };
     let mut parameter_template = parameter_template.as_str();
 
+    let xml_template = xml_parameter_template(&body);
+
     if parameter_template.is_empty() {
         if body_type.contains("json") && args.is_present("as-body") {
             parameter_template = "\"%k\":\"%v\", ";
+        } else if body_type.contains("xml") && args.is_present("as-body") {
+            parameter_template = &xml_template;
+        } else if body_type.contains("multipart") && args.is_present("as-body") {
+            parameter_template = &multipart_template;
+        } else if args.value_of("path-injection") == Some("matrix") {
+            parameter_template = ";%k=%v";
+        } else if args.value_of("path-injection") == Some("segments") {
+            parameter_template = "/%k/%v";
         } else if within_headers {
             parameter_template = "%k=%v; ";
         } else {
@@ -457,27 +655,17 @@ pub fn get_config() -> (Config, usize) {
     }
 
 
-    let request = match args.value_of("request") {
-        Some(val) => match fs::read_to_string(val) {
-            Ok(val) => val,
-            Err(err) => {
-                writeln!(io::stderr(), "Unable to open request file: {}", err).ok();
-                std::process::exit(1);
-            }
-        },
-        None => String::new(),
-    };
-
     if args.is_present("disable-colors") {
         colored::control::set_override(false);
     }
 
-    let mut config = Config {
+    //url related fields are filled for every target separately
+    let config = Config {
         method: args.value_of("method").unwrap().to_string(),
-        initial_url: args.value_of("url").unwrap_or("").to_string(),
-        url,
-        host: host.to_string(),
-        path,
+        initial_url: String::new(),
+        url: String::new(),
+        host: String::new(),
+        path: String::new(),
         wordlist: args.value_of("wordlist").unwrap_or("").to_string(),
         parameter_template: parameter_template.to_string(),
         custom_parameters,
@@ -491,9 +679,10 @@ pub fn get_config() -> (Config, usize) {
         save_responses: args.value_of("save-responses").unwrap_or("").to_string(),
         output_format: args.value_of("output-format").unwrap_or("").to_string(),
         append: args.is_present("append"),
-        as_body: args.is_present("as-body"),
+        as_body: args.is_present("as-body") || args.is_present("graphql"),
         headers_discovery: args.is_present("headers-discovery"),
         within_headers,
+        cookies_discovery: args.is_present("cookies"),
         force: args.is_present("force"),
         disable_response_correction: args.is_present("disable-response-correction"),
         disable_custom_parameters: args.is_present("disable-custom-parameters"),
@@ -509,27 +698,181 @@ pub fn get_config() -> (Config, usize) {
         learn_requests_count,
         max,
         concurrency,
+        workers,
+        //the configs of every target share the same limit
+        requests_limit: Some(Arc::new(Semaphore::new(concurrency))),
         verify: args.is_present("verify"),
-        reflected_only: args.is_present("reflected_only")
+        reflected_only: args.is_present("reflected_only"),
+        expected_parameters: match args.values_of("expected-parameters") {
+            Some(val) => val.map(|x| x.to_string()).collect(),
+            None => Vec::new()
+        },
+        documented_parameters: None,
+        json_path: String::new(),
+        typed_values: args.is_present("typed"),
+        graphql: String::new(),
+        graphql_field: String::new(),
+        preset: args.value_of("preset").unwrap_or("").to_string(),
+        parents: match args.values_of("parents") {
+            Some(val) => val.map(|x| x.to_string()).collect(),
+            None => Vec::new()
+        },
+        path_injection: args.value_of("path-injection").unwrap_or("").to_string(),
+        locations: if args.is_present("mixed") {
+            Location::MIXED.to_vec()
+        } else {
+            Vec::new()
+        },
+        hpp: args.is_present("hpp"),
+        masks,
+        timing: args.is_present("timing"),
+        special_chars: args.is_present("special-chars"),
+        enumerate_values: match args.values_of("enumerate") {
+            Some(val) => COMMON_VALUES
+                .iter()
+                .map(|x| x.to_string())
+                .chain(val.map(|x| x.to_string()).filter(|x| !COMMON_VALUES.contains(&x.as_str())))
+                .collect(),
+            None if args.is_present("enumerate") => COMMON_VALUES.iter().map(|x| x.to_string()).collect(),
+            None => Vec::new()
+        },
     };
 
-    config = if !request.is_empty() {
-        match parse_request(
-            config,
-             args.value_of("proto").unwrap_or("https"),
-              &request, !args.value_of("parameter_template").unwrap_or("").is_empty()
-        ) {
-            Some(val) => val,
-            None => {
-                writeln!(io::stderr(), "Unable to parse request file.").ok();
+    let mut urls: Vec<String> = Vec::new();
+
+    if let Some(val) = args.value_of("url") {
+        urls.push(val.to_string());
+    }
+
+    match args.value_of("urls") {
+        Some("-") => {
+            for line in io::stdin().lock().lines().map_while(Result::ok) {
+                urls.push(line);
+            }
+        },
+        Some(val) => match read_lines(val) {
+            Ok(lines) => {
+                for line in lines.map_while(Result::ok) {
+                    urls.push(line);
+                }
+            },
+            Err(err) => {
+                writeln!(io::stderr(), "Unable to open urls file: {}", err).ok();
                 std::process::exit(1);
             }
+        },
+        None => ()
+    }
+
+    let mut configs: Vec<Config> = Vec::new();
+
+    for url in urls.iter().map(|x| x.trim()).filter(|x| !x.is_empty()) {
+        match parse_url(config.clone(), url) {
+            Ok(val) => configs.push(val),
+            Err(err) => {
+                writeln!(io::stderr(), "{}", err).ok();
+                //a single broken url among many shouldn't stop the whole scan
+                if args.value_of("url").is_some() {
+                    std::process::exit(err.exit_code());
+                }
+            }
         }
-    } else {
-        config
+    }
+
+    if let Some(val) = args.values_of("request") {
+        for filename in val {
+            let request = match fs::read_to_string(filename) {
+                Ok(val) => val,
+                Err(err) => {
+                    writeln!(io::stderr(), "Unable to open request file: {}", err).ok();
+                    std::process::exit(1);
+                }
+            };
+
+            match parse_request(
+                config.clone(),
+                args.value_of("proto").unwrap_or("https"),
+                &request, !args.value_of("parameter_template").unwrap_or("").is_empty()
+            ) {
+                Ok(val) => configs.push(val),
+                Err(err) => {
+                    writeln!(io::stderr(), "{} {}.", err, filename).ok();
+                    std::process::exit(err.exit_code());
+                }
+            }
+        }
+    }
+
+    if let Some(val) = args.value_of("curl") {
+        match from_curl(&config, val, !args.value_of("parameter_template").unwrap_or("").is_empty()) {
+            Ok(val) => configs.push(val),
+            Err(err) => {
+                writeln!(io::stderr(), "{}", err).ok();
+                std::process::exit(err.exit_code());
+            }
+        }
+    }
+
+    let filter = Filter {
+        host: parse_regex(&args, "filter-host"),
+        path: parse_regex(&args, "filter-path"),
     };
 
-    (config, max)
+    for (format, filename) in [("burp", args.value_of("burp")), ("har", args.value_of("har")), ("openapi", args.value_of("openapi"))] {
+        let filename = match filename {
+            Some(val) => val,
+            None => continue
+        };
+
+        let data = match fs::read_to_string(filename) {
+            Ok(val) => val,
+            Err(err) => {
+                writeln!(io::stderr(), "Unable to open {} file: {}", format, err).ok();
+                std::process::exit(1);
+            }
+        };
+
+        let custom_parameter_template = !args.value_of("parameter_template").unwrap_or("").is_empty();
+
+        let imported = match format {
+            "burp" => from_burp(&config, &data, &filter, custom_parameter_template),
+            "har" => from_har(&config, &data, &filter, custom_parameter_template),
+            _ => from_openapi(&config, &data, args.value_of("openapi-base").unwrap_or(""), &filter, custom_parameter_template),
+        };
+
+        match imported {
+            Ok(mut val) => configs.append(&mut val),
+            Err(err) => {
+                writeln!(io::stderr(), "{} {}.", err, filename).ok();
+                std::process::exit(err.exit_code());
+            }
+        }
+    }
+
+    let configs: Vec<Config> = configs
+        .into_iter()
+        .flat_map(|x| match args.value_of("graphql") {
+            Some(query) => graphql_configs(x, query, args.value_of("graphql-field").unwrap()),
+            None => json_body_configs(x, args.is_present("nested")),
+        })
+        .collect();
+
+    if configs.is_empty() {
+        writeln!(io::stderr(), "A target was not provided").ok();
+        std::process::exit(1);
+    }
+
+    configs
+}
+
+fn parse_regex(args: &clap::ArgMatches, value: &str) -> Option<Regex> {
+    match Regex::new(args.value_of(value)?) {
+        Ok(val) => Some(val),
+        Err(err) => {
+            writeln!(io::stderr(), "Unable to parse '{}' value: {}", value, err).ok();
+            std::process::exit(1);
+        }
+    }
 }
 
 fn parse_int(args: &clap::ArgMatches, value: &str) -> usize {
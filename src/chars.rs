@@ -0,0 +1,130 @@
+use crate::{
+    error::X8Error,
+    reflections::count_reflections,
+    requests::request_pairs,
+    structs::{CharStatus, Config, Location, SpecialChar, Statistic},
+    utils::random_line,
+};
+use reqwest::Client;
+
+//chars that matter for xss and template injections
+pub const SPECIAL_CHARS: [char; 7] = ['<', '>', '"', '\'', '`', '{', '\\'];
+
+//the maximum length of an encoded char like &#x0003c;
+const MAX_ENCODED_LENGTH: usize = 10;
+
+//sends every special char between two random strings and checks how the char is reflected
+pub async fn probe_special_chars(
+    config: &Config,
+    stats: &mut Statistic,
+    client: &Client,
+    name: &str,
+    reflections: usize,
+) -> Result<Vec<SpecialChar>, X8Error> {
+    let mut special_chars: Vec<SpecialChar> = Vec::with_capacity(SPECIAL_CHARS.len());
+
+    //the chars are checked within the raw response, beautify_json replaces \" with '
+    let config = &Config {
+        disable_response_correction: true,
+        ..config.clone()
+    };
+
+    for special_char in SPECIAL_CHARS {
+        let prefix = random_line(config.value_size);
+        let suffix = random_line(config.value_size);
+
+        let value = [prefix.as_str(), &escape_json(config, special_char), suffix.as_str()].concat();
+        let response = request_pairs(config, stats, client, &[(name.to_string(), value)], reflections).await?;
+
+        let status = if count_reflections(&response.text, &response.headers, &prefix) <= reflections {
+            CharStatus::NotReflected
+        } else {
+            let text = response.full_text().to_ascii_lowercase();
+
+            //the most useful reflection is reported when there are a few of them
+            text.match_indices(&prefix)
+                .map(|(position, _)| char_status(&text[position + prefix.len()..], special_char, &suffix))
+                .min()
+                .unwrap_or(CharStatus::NotReflected)
+        };
+
+        special_chars.push(SpecialChar {
+            special_char,
+            status,
+        });
+    }
+
+    Ok(special_chars)
+}
+
+//the text is the part of the response right after the prefix
+fn char_status(text: &str, special_char: char, suffix: &str) -> CharStatus {
+    let between = match text.char_indices().take(MAX_ENCODED_LENGTH + 1).find(|(i, _)| text[*i..].starts_with(suffix)) {
+        Some((i, _)) => &text[..i],
+        None => return CharStatus::Replaced
+    };
+
+    if between.is_empty() {
+        CharStatus::Stripped
+    } else if between.len() == 1 && between.starts_with(special_char) {
+        CharStatus::Raw
+    } else if between.starts_with('\\') {
+        CharStatus::Escaped
+    } else if between.starts_with('&') && between.ends_with(';') {
+        CharStatus::HtmlEncoded
+    } else if between.starts_with('%') {
+        CharStatus::UrlEncoded
+    } else {
+        CharStatus::Replaced
+    }
+}
+
+//quotes and backslashes would break json bodies
+fn escape_json(config: &Config, special_char: char) -> String {
+    if config.location() == Location::Body && config.body_type.contains("json") && matches!(special_char, '"' | '\\') {
+        format!("\\{}", special_char)
+    } else {
+        special_char.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn char_status_raw_and_escaped() {
+        assert_eq!(char_status("<suffix</p>", '<', "suffix"), CharStatus::Raw);
+        assert_eq!(char_status("\\suffix", '\\', "suffix"), CharStatus::Raw);
+        assert_eq!(char_status("\\\"suffix", '"', "suffix"), CharStatus::Escaped);
+        assert_eq!(char_status("\\u0022suffix", '"', "suffix"), CharStatus::Escaped);
+        assert_eq!(char_status("\\\\suffix", '\\', "suffix"), CharStatus::Escaped);
+    }
+
+    #[test]
+    fn char_status_encoded() {
+        assert_eq!(char_status("&lt;suffix", '<', "suffix"), CharStatus::HtmlEncoded);
+        assert_eq!(char_status("&#x0003c;suffix", '<', "suffix"), CharStatus::HtmlEncoded);
+        assert_eq!(char_status("%3csuffix", '<', "suffix"), CharStatus::UrlEncoded);
+        assert_eq!(char_status("?suffix", '<', "suffix"), CharStatus::Replaced);
+    }
+
+    #[test]
+    fn char_status_stripped_and_missing() {
+        assert_eq!(char_status("suffix", '<', "suffix"), CharStatus::Stripped);
+        //the suffix is too far away to be after an encoded char
+        assert_eq!(char_status("&lt;&lt;&lt;suffix", '<', "suffix"), CharStatus::Replaced);
+        assert_eq!(char_status("<", '<', "suffix"), CharStatus::Replaced);
+    }
+
+    #[test]
+    fn json_bodies_escape_quotes() {
+        let config = Config { as_body: true, body_type: String::from("json-"), ..Config::default() };
+        assert_eq!(escape_json(&config, '"'), "\\\"");
+        assert_eq!(escape_json(&config, '\\'), "\\\\");
+        assert_eq!(escape_json(&config, '<'), "<");
+
+        let config = Config { as_body: false, ..config };
+        assert_eq!(escape_json(&config, '"'), "\"");
+    }
+}
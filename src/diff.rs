@@ -175,4 +175,213 @@ impl diffs::Diff for Processor {
             .append(&mut self.context.to_vec(self.removed, self.inserted));
         Ok(())
     }
-}
\ No newline at end of file
+}
+
+//unified diff with `context` unchanged lines around every hunk
+pub fn unified_diff(
+    text1: &str,
+    text2: &str,
+    context: usize,
+) -> io::Result<Vec<String>> {
+    let old = text1.lines().collect::<Vec<&str>>();
+    let new = text2.lines().collect::<Vec<&str>>();
+
+    let mut collector = Collector { old: &old, new: &new, lines: Vec::new() };
+    {
+        let mut replace = diffs::Replace::new(&mut collector);
+        diffs::myers::diff(&mut replace, &old, &new)?;
+    }
+    let lines = collector.lines;
+
+    //(old line, new line) numbers before every line
+    let mut positions = Vec::with_capacity(lines.len());
+    let (mut old_line, mut new_line) = (0, 0);
+    for line in lines.iter() {
+        positions.push((old_line, new_line));
+        match line.chars().next() {
+            Some('-') => old_line += 1,
+            Some('+') => new_line += 1,
+            _ => {
+                old_line += 1;
+                new_line += 1;
+            }
+        }
+    }
+
+    let changed = lines
+        .iter()
+        .enumerate()
+        .filter(|(_, line)| !line.starts_with(' '))
+        .map(|(i, _)| i)
+        .collect::<Vec<usize>>();
+
+    let mut result = Vec::new();
+    let mut i = 0;
+    while i < changed.len() {
+        let start = changed[i].saturating_sub(context);
+        let mut end = changed[i];
+
+        //join hunks that are close to each other
+        while i + 1 < changed.len() && changed[i + 1] <= end + context * 2 + 1 {
+            i += 1;
+            end = changed[i];
+        }
+        let end = (end + context + 1).min(lines.len());
+
+        let hunk = &lines[start..end];
+        let removed = hunk.iter().filter(|x| !x.starts_with('+')).count();
+        let inserted = hunk.iter().filter(|x| !x.starts_with('-')).count();
+
+        //empty ranges point to the line before them, like in diff -u
+        result.push(format!(
+            "@@ -{},{} +{},{} @@",
+            positions[start].0 + (removed > 0) as usize,
+            removed,
+            positions[start].1 + (inserted > 0) as usize,
+            inserted
+        ));
+        result.extend_from_slice(hunk);
+
+        i += 1;
+    }
+
+    Ok(result)
+}
+
+struct Collector<'a> {
+    old: &'a [&'a str],
+    new: &'a [&'a str],
+    lines: Vec<String>,
+}
+
+impl diffs::Diff for Collector<'_> {
+    type Error = io::Error;
+
+    fn equal(&mut self, old: usize, _new: usize, len: usize) -> Result<(), Self::Error> {
+        for line in &self.old[old..old + len] {
+            self.lines.push(format!(" {}", line));
+        }
+        Ok(())
+    }
+
+    fn delete(&mut self, old: usize, len: usize) -> Result<(), Self::Error> {
+        for line in &self.old[old..old + len] {
+            self.lines.push(format!("-{}", line));
+        }
+        Ok(())
+    }
+
+    fn insert(&mut self, _old: usize, new: usize, new_len: usize) -> Result<(), Self::Error> {
+        for line in &self.new[new..new + new_len] {
+            self.lines.push(format!("+{}", line));
+        }
+        Ok(())
+    }
+
+    fn replace(
+        &mut self,
+        old: usize,
+        old_len: usize,
+        new: usize,
+        new_len: usize,
+    ) -> Result<(), Self::Error> {
+        self.delete(old, old_len)?;
+        self.insert(old, new, new_len)
+    }
+}
+
+//pairs of lines that were replaced by the same amount of lines
+pub fn changed_lines(
+    text1: &str,
+    text2: &str,
+) -> io::Result<Vec<(String, String)>> {
+    let old = text1.lines().collect::<Vec<&str>>();
+    let new = text2.lines().collect::<Vec<&str>>();
+
+    let mut pairs = Pairs { old: &old, new: &new, pairs: Vec::new() };
+    {
+        let mut replace = diffs::Replace::new(&mut pairs);
+        diffs::myers::diff(&mut replace, &old, &new)?;
+    }
+
+    Ok(pairs.pairs)
+}
+
+struct Pairs<'a> {
+    old: &'a [&'a str],
+    new: &'a [&'a str],
+    pairs: Vec<(String, String)>,
+}
+
+impl diffs::Diff for Pairs<'_> {
+    type Error = io::Error;
+
+    fn replace(
+        &mut self,
+        old: usize,
+        old_len: usize,
+        new: usize,
+        new_len: usize,
+    ) -> Result<(), Self::Error> {
+        if old_len == new_len {
+            for i in 0..old_len {
+                self.pairs.push((self.old[old + i].to_string(), self.new[new + i].to_string()));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn numbered(changes: &[(usize, &str)]) -> String {
+        (1..=20)
+            .map(|i| match changes.iter().find(|(line, _)| *line == i) {
+                Some((_, text)) => text.to_string(),
+                None => format!("line{}", i),
+            })
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+
+    #[test]
+    fn unified_diff_hunks() {
+        let old = numbered(&[]);
+        let new = numbered(&[(2, "changed2"), (5, "changed5"), (16, "changed16")]);
+
+        //the first two changes are close enough to share a hunk
+        assert_eq!(
+            unified_diff(&old, &new, 1).unwrap(),
+            vec![
+                "@@ -1,6 +1,6 @@", " line1", "-line2", "+changed2", " line3", " line4", "-line5", "+changed5", " line6",
+                "@@ -15,3 +15,3 @@", " line15", "-line16", "+changed16", " line17",
+            ]
+        );
+    }
+
+    #[test]
+    fn unified_diff_insertions() {
+        let old = "a\nb\nc";
+        let new = "a\nb\nnew\nc\nd";
+
+        assert_eq!(
+            unified_diff(old, new, 0).unwrap(),
+            vec!["@@ -2,0 +3,1 @@", "+new", "@@ -3,0 +5,1 @@", "+d"]
+        );
+        assert!(unified_diff(old, old, 3).unwrap().is_empty());
+    }
+
+    #[test]
+    fn changed_lines_pairs() {
+        let old = "a\ntoken=1\nb\nx\ny\nc";
+        let new = "a\ntoken=2\nb\nz\nc\nd";
+
+        //only replacements with the same amount of lines are paired
+        assert_eq!(
+            changed_lines(old, new).unwrap(),
+            vec![(String::from("token=1"), String::from("token=2"))]
+        );
+    }
+}
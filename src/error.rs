@@ -0,0 +1,77 @@
+use std::{error::Error, fmt, io};
+
+#[derive(Debug)]
+pub enum X8Error {
+    //the response code changed while the page was requested with random parameters
+    UnstableCode(String),
+    //the body changed between requests with random parameters and masking didn't help
+    UnstableBody(String),
+    UnstableReflections(String),
+    PageTooLarge(String),
+    UnsupportedMethod(String),
+    Unreachable(String),
+    RequestFileParse,
+    InvalidUrl(String, url::ParseError),
+    //the amount of remaining parameters doesn't decrease
+    InfinityLoop(String),
+    Diff(io::Error),
+    Client(reqwest::Error),
+    //unable to read a burp or har export
+    Import(String),
+}
+
+impl X8Error {
+    //every variant has its own exit code so scripts can tell the failures apart
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            X8Error::UnstableCode(_) => 2,
+            X8Error::UnstableReflections(_) => 3,
+            X8Error::PageTooLarge(_) => 4,
+            X8Error::UnsupportedMethod(_) => 5,
+            X8Error::Unreachable(_) => 6,
+            X8Error::RequestFileParse => 7,
+            X8Error::InvalidUrl(_, _) => 8,
+            X8Error::InfinityLoop(_) => 9,
+            X8Error::Diff(_) => 10,
+            X8Error::Client(_) => 11,
+            X8Error::Import(_) => 12,
+            X8Error::UnstableBody(_) => 13,
+        }
+    }
+}
+
+impl fmt::Display for X8Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            X8Error::UnstableCode(url) => write!(f, "{} the page is not stable (code)", url),
+            X8Error::UnstableBody(url) => write!(f, "{} the page is not stable (body)", url),
+            X8Error::UnstableReflections(url) => write!(f, "{} Reflections are not stable", url),
+            X8Error::PageTooLarge(url) => write!(f, "{} the page is too huge", url),
+            X8Error::UnsupportedMethod(method) => write!(f, "Method {} is not supported", method),
+            X8Error::Unreachable(url) => write!(f, "unable to reach {}", url),
+            X8Error::RequestFileParse => write!(f, "Unable to parse request file"),
+            X8Error::InvalidUrl(url, err) => write!(f, "Unable to parse target url {}: {}", url, err),
+            X8Error::InfinityLoop(url) => write!(f, "{} Infinity loop detected", url),
+            X8Error::Diff(err) => write!(f, "Unable to compare: {}", err),
+            X8Error::Client(err) => write!(f, "Unable to build a client: {}", err),
+            X8Error::Import(err) => write!(f, "Unable to import requests: {}", err),
+        }
+    }
+}
+
+impl Error for X8Error {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            X8Error::InvalidUrl(_, err) => Some(err),
+            X8Error::Diff(err) => Some(err),
+            X8Error::Client(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<reqwest::Error> for X8Error {
+    fn from(err: reqwest::Error) -> Self {
+        X8Error::Client(err)
+    }
+}
@@ -0,0 +1,289 @@
+use crate::{
+    structs::{Config, GraphqlErrors, Reason},
+    utils::remove_query_injection,
+};
+use lazy_static::lazy_static;
+use regex::Regex;
+
+lazy_static! {
+    //graphql-js: Unknown argument "x" on field "Query.user".
+    //graphql-java: Unknown field argument 'x'
+    static ref RE_UNKNOWN_ARGUMENT: Regex = Regex::new(r#"Unknown (?:field )?argument ["'](\w+)["']"#).unwrap();
+    //graphql-js: Cannot query field "x" on type "User".
+    //graphql-java: Field 'x' in type 'User' is undefined
+    static ref RE_UNKNOWN_FIELD: Regex = Regex::new(r#"Cannot query field ["'](\w+)["']|Field ["'](\w+)["'] in type ["']\w+["'] is undefined"#).unwrap();
+    static ref RE_SUGGESTIONS: Regex = Regex::new(r"Did you mean (.+?)\?").unwrap();
+    static ref RE_QUOTED_NAME: Regex = Regex::new(r#"["'](\w+)["']"#).unwrap();
+    static ref RE_NAME: Regex = Regex::new(r"[_A-Za-z][_0-9A-Za-z]*").unwrap();
+}
+
+//the amount of candidates in a single request. graphql-js stops the validation after 100 errors
+const GRAPHQL_MAX: usize = 64;
+
+//creates a config for the arguments and another one for the selection fields of the target field.
+//The query is sent as {"query": "..."} within POST requests
+pub fn graphql_configs(config: Config, query: &str, field: &str) -> Vec<Config> {
+    let mut configs: Vec<Config> = Vec::with_capacity(2);
+
+    for kind in ["arguments", "fields"] {
+        let query = match inject(query, field, kind) {
+            Some(val) => val,
+            //scalar fields don't have a selection set
+            None => continue
+        };
+
+        let mut headers = config.headers.clone();
+        if !headers.keys().any(|x| x.eq_ignore_ascii_case("Content-Type")) {
+            headers.insert(String::from("Content-Type"), String::from("application/json"));
+        }
+
+        configs.push(Config {
+            method: if config.method == "GET" { String::from("POST") } else { config.method.clone() },
+            url: remove_query_injection(&config.url),
+            path: remove_query_injection(&config.path),
+            headers,
+            body: serde_json::json!({ "query": query }).to_string(),
+            body_type: String::from("graphql"),
+            parameter_template: match kind {
+                //the value is a string within the query that is a json string itself
+                "arguments" => String::from("%k:\\\"%v\\\" "),
+                _ => String::from("%k "),
+            },
+            as_body: true,
+            max: config.max.min(GRAPHQL_MAX),
+            disable_custom_parameters: true,
+            graphql: kind.to_string(),
+            graphql_field: field.to_string(),
+            ..config.clone()
+        });
+    }
+
+    configs
+}
+
+//puts %s within the arguments or the selection set of the first occurrence of the field
+fn inject(query: &str, field: &str, kind: &str) -> Option<String> {
+    let re = Regex::new(&format!(r"\b{}\b", regex::escape(field))).ok()?;
+    let mut offset = re.find(query)?.end();
+
+    let skip_whitespaces = |offset: usize| offset + query[offset..].len() - query[offset..].trim_start().len();
+
+    if kind == "arguments" {
+        let start = skip_whitespaces(offset);
+        return if query[start..].starts_with('(') {
+            Some([&query[..start + 1], "%s ", &query[start + 1..]].concat())
+        } else {
+            Some([&query[..offset], "(%s)", &query[offset..]].concat())
+        }
+    }
+
+    //the selection set goes after the arguments
+    let start = skip_whitespaces(offset);
+    if query[start..].starts_with('(') {
+        offset = start + query[start..].find(')')? + 1;
+    }
+
+    let start = skip_whitespaces(offset);
+    if query[start..].starts_with('{') {
+        Some([&query[..start + 1], " %s", &query[start + 1..]].concat())
+    } else {
+        None
+    }
+}
+
+//only valid names can be put into the query without breaking it
+pub fn is_graphql_name(name: &str) -> bool {
+    RE_NAME.find(name).is_some_and(|x| x.start() == 0 && x.end() == name.len())
+}
+
+//names that are already used within the query. They don't need to be checked
+pub fn query_names(config: &Config) -> Vec<String> {
+    RE_NAME
+        .find_iter(&config.body)
+        .map(|x| x.as_str().to_string())
+        .collect()
+}
+
+//removes errors about unknown arguments or fields from the response body, so the usual comparison isn't affected by them.
+//Returns the new body and the names from the removed errors
+pub fn split_errors(body: &str, kind: &str) -> (String, GraphqlErrors) {
+    let mut errors = GraphqlErrors::default();
+
+    let mut value: serde_json::Value = match serde_json::from_str(body) {
+        Ok(val) => val,
+        Err(_) => return (body.to_string(), errors)
+    };
+
+    let list = match value.get_mut("errors").and_then(|x| x.as_array_mut()) {
+        Some(val) => val,
+        None => return (body.to_string(), errors)
+    };
+
+    let re = if kind == "arguments" { &*RE_UNKNOWN_ARGUMENT } else { &*RE_UNKNOWN_FIELD };
+
+    list.retain(|error| {
+        let message = error.get("message").and_then(|x| x.as_str()).unwrap_or("");
+
+        if message.contains("error limit reached") || message.contains("Too many validation errors") {
+            errors.truncated = true;
+            return false
+        }
+
+        let name = match re.captures(message).and_then(|x| x.iter().skip(1).flatten().next()) {
+            Some(val) => val.as_str().to_string(),
+            None => return true
+        };
+
+        if let Some(suggestions) = RE_SUGGESTIONS.captures(message) {
+            for suggestion in RE_QUOTED_NAME.captures_iter(&suggestions[1]) {
+                if !errors.suggestions.contains(&suggestion[1].to_string()) {
+                    errors.suggestions.push(suggestion[1].to_string());
+                }
+            }
+        }
+
+        errors.unknown.push(name);
+        false
+    });
+
+    if list.is_empty() {
+        if let Some(object) = value.as_object_mut() {
+            object.remove("errors");
+        }
+    }
+
+    (value.to_string(), errors)
+}
+
+//candidates that weren't reported as unknown exist.
+//Suggested names exist as well even if they aren't within the wordlist.
+//None means that the errors were truncated and the chunk needs to be split
+pub fn graphql_findings(config: &Config, chunk: &[String], errors: &GraphqlErrors) -> Option<Vec<(String, Reason)>> {
+    if errors.truncated {
+        return None
+    }
+
+    let mut findings: Vec<(String, Reason)> = Vec::new();
+
+    //without unknown names the query wasn't validated at all, e.g. because of a syntax error
+    if !errors.unknown.is_empty() {
+        for name in chunk.iter().filter(|x| !errors.unknown.contains(x)) {
+            findings.push((name.to_string(), Reason::GraphqlAccepted));
+        }
+    }
+
+    let known = query_names(config);
+    for name in errors.suggestions.iter() {
+        if !known.contains(name) && !findings.iter().any(|(x, _)| x == name) {
+            findings.push((name.to_string(), Reason::GraphqlSuggested));
+        }
+    }
+
+    Some(findings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn inject_arguments() {
+        assert_eq!(inject("{ user { name } }", "user", "arguments").unwrap(), "{ user(%s) { name } }");
+        assert_eq!(inject("{ user (id: 1) { name } }", "user", "arguments").unwrap(), "{ user (%s id: 1) { name } }");
+        //only whole names are matched
+        assert_eq!(inject("{ users { name } }", "user", "arguments"), None);
+    }
+
+    #[test]
+    fn inject_fields() {
+        assert_eq!(inject("{ user { name } }", "user", "fields").unwrap(), "{ user { %s name } }");
+        assert_eq!(inject("{ user(id: 1) { name } }", "user", "fields").unwrap(), "{ user(id: 1) { %s name } }");
+        assert_eq!(inject("{ user { name } }", "name", "fields"), None);
+    }
+
+    #[test]
+    fn graphql_configs_post_json() {
+        let config = Config {
+            method: String::from("GET"),
+            url: String::from("https://example.com/graphql?%s"),
+            path: String::from("/graphql?%s"),
+            max: 128,
+            ..Config::default()
+        };
+
+        let configs = graphql_configs(config, "{ user { name } }", "user");
+        assert_eq!(configs.len(), 2);
+
+        assert_eq!(configs[0].method, "POST");
+        assert_eq!(configs[0].url, "https://example.com/graphql");
+        assert_eq!(configs[0].headers.get("Content-Type").unwrap(), "application/json");
+        assert_eq!(configs[0].body, r#"{"query":"{ user(%s) { name } }"}"#);
+        assert_eq!(configs[0].graphql, "arguments");
+        assert_eq!(configs[0].max, GRAPHQL_MAX);
+        assert_eq!(configs[1].graphql, "fields");
+
+        //scalar fields only get the arguments config
+        let configs = graphql_configs(Config::default(), "{ user { name } }", "name");
+        assert_eq!(configs.len(), 1);
+    }
+
+    #[test]
+    fn split_errors_unknown_and_suggestions() {
+        let body = r#"{"errors":[
+            {"message":"Unknown argument \"admin\" on field \"Query.user\". Did you mean \"isAdmin\" or \"role\"?"},
+            {"message":"Unknown field argument 'debug'"},
+            {"message":"Something else"}
+        ],"data":null}"#;
+
+        let (body, errors) = split_errors(body, "arguments");
+        assert_eq!(errors.unknown, vec!["admin", "debug"]);
+        assert_eq!(errors.suggestions, vec!["isAdmin", "role"]);
+        assert!(!errors.truncated);
+        assert_eq!(body, r#"{"errors":[{"message":"Something else"}],"data":null}"#);
+    }
+
+    #[test]
+    fn split_errors_fields() {
+        let body = r#"{"errors":[
+            {"message":"Cannot query field \"secret\" on type \"User\"."},
+            {"message":"Field 'token' in type 'User' is undefined"}
+        ]}"#;
+
+        let (body, errors) = split_errors(body, "fields");
+        assert_eq!(errors.unknown, vec!["secret", "token"]);
+        //the errors key is removed when nothing is left
+        assert_eq!(body, "{}");
+
+        //argument errors aren't removed while looking for fields
+        let body = r#"{"errors":[{"message":"Unknown argument \"admin\" on field \"Query.user\"."}]}"#;
+        let (_, errors) = split_errors(body, "fields");
+        assert!(errors.unknown.is_empty());
+    }
+
+    #[test]
+    fn split_errors_truncated_and_invalid() {
+        let body = r#"{"errors":[{"message":"Too many validation errors, error limit reached. Validation aborted."}]}"#;
+        assert!(split_errors(body, "arguments").1.truncated);
+
+        let (body, errors) = split_errors("<html>", "arguments");
+        assert_eq!(body, "<html>");
+        assert!(errors.unknown.is_empty());
+    }
+
+    #[test]
+    fn findings_from_errors() {
+        let config = Config { body: String::from(r#"{"query":"{ user { name } }"}"#), ..Config::default() };
+        let chunk = vec![String::from("admin"), String::from("debug")];
+
+        let errors = GraphqlErrors {
+            unknown: vec![String::from("admin")],
+            suggestions: vec![String::from("name"), String::from("role")],
+            truncated: false,
+        };
+        let findings: Vec<String> = graphql_findings(&config, &chunk, &errors).unwrap().into_iter().map(|(x, _)| x).collect();
+        assert_eq!(findings, vec!["debug", "role"]);
+
+        let errors = GraphqlErrors { truncated: true, ..GraphqlErrors::default() };
+        assert!(graphql_findings(&config, &chunk, &errors).is_none());
+    }
+}
@@ -0,0 +1,306 @@
+use crate::{
+    error::X8Error,
+    reflections::count_reflections,
+    requests::request_pairs,
+    structs::{Config, Location, Pollution, Precedence, ResponseData, Statistic},
+    utils::{
+        adjust_multipart_body, compare, location_config, make_body, multipart_parameter_template, random_line,
+        set_multipart_content_type,
+    },
+};
+use reqwest::Client;
+
+//sends duplicates of a found parameter and checks which of the values are used.
+//Only the query and the body can contain duplicates
+#[allow(clippy::too_many_arguments)]
+pub async fn check_pollution(
+    config: &Config,
+    stats: &mut Statistic,
+    client: &Client,
+    param: &str,
+    initial_response: &ResponseData,
+    diffs: &[String],
+    reflections: usize,
+) -> Result<Option<Pollution>, X8Error> {
+    if !matches!(config.location(), Location::Query | Location::Body) {
+        return Ok(None)
+    }
+
+    //parameters with custom values are checked by their names
+    let (name, value) = param.split_once('=').unwrap_or((param, ""));
+
+    let first = random_line(config.value_size);
+    let second = random_line(config.value_size);
+
+    let response = request_pairs(config, stats, client, &[pair(name, &first)], reflections).await?;
+    if count_reflections(&response.text, &response.headers, &first) <= reflections {
+        let page = Page { initial_response, first: response, second: None, diffs };
+        return pollution_by_changes(config, stats, client, name, value, page, &first, reflections).await.map(Some)
+    }
+
+    let query_and_body_config = query_and_body_config(config, name, &second);
+
+    let response = request_pairs(
+        config, stats, client, &[pair(name, &first), pair(name, &second)], reflections
+    ).await?;
+    let duplicates = precedence(&response.full_text(), &first, &second, reflections);
+
+    let array_name = format!("{}[]", name);
+    let response = request_pairs(
+        config, stats, client, &[pair(&array_name, &first), pair(&array_name, &second)], reflections
+    ).await?;
+    let array = precedence(&response.full_text(), &first, &second, reflections);
+
+    let query_and_body = match query_and_body_config {
+        Some(val) => {
+            let response = request_pairs(&val, stats, client, &[pair(name, &first)], reflections).await?;
+            Some(precedence(&response.full_text(), &first, &second, reflections))
+        },
+        None => None
+    };
+
+    Ok(Some(Pollution {
+        duplicates,
+        array,
+        query_and_body,
+    }))
+}
+
+//the pages the responses to duplicates are matched against
+struct Page<'a> {
+    initial_response: &'a ResponseData,
+    first: ResponseData,
+    second: Option<ResponseData>,
+    diffs: &'a [String],
+}
+
+impl Page<'_> {
+    //the same code and no diffs except for the ones the page has without parameters
+    fn is_same(&self, a: &ResponseData, b: &ResponseData) -> Result<bool, X8Error> {
+        let (is_code_the_same, new_diffs) = compare(a, b)?;
+        Ok(is_code_the_same && new_diffs.iter().all(|x| self.diffs.contains(x)))
+    }
+
+    fn precedence(&self, response: &ResponseData) -> Result<Precedence, X8Error> {
+        let second = match &self.second {
+            Some(val) => val,
+            None => return Ok(Precedence::Unknown)
+        };
+
+        Ok(if self.is_same(&self.first, response)? {
+            Precedence::First
+        } else if self.is_same(second, response)? {
+            Precedence::Last
+        } else if self.is_same(self.initial_response, response)? {
+            Precedence::Ignored
+        } else {
+            Precedence::Unknown
+        })
+    }
+}
+
+//values that aren't reflected can't show which copy is used, so the pages they lead to are compared instead.
+//It needs a second value that changes the page differently from a random one
+#[allow(clippy::too_many_arguments)]
+async fn pollution_by_changes(
+    config: &Config,
+    stats: &mut Statistic,
+    client: &Client,
+    name: &str,
+    value: &str,
+    mut page: Page<'_>,
+    first: &str,
+    reflections: usize,
+) -> Result<Pollution, X8Error> {
+    let initial_response = page.initial_response;
+    let mut second = String::new();
+    let mut candidates = vec![value, "", "0", "1"];
+    candidates.dedup();
+
+    for candidate in candidates {
+        let response = request_pairs(config, stats, client, &[pair(name, candidate)], reflections).await?;
+        //the last value can't be told apart from an ignored one if it leads to the initial page
+        if !page.is_same(&page.first, &response)? && !page.is_same(initial_response, &response)? {
+            second = candidate.to_string();
+            page.second = Some(response);
+            break
+        }
+    }
+
+    //both values look the same
+    if page.second.is_none() {
+        return Ok(Pollution {
+            duplicates: Precedence::Unknown,
+            array: Precedence::Unknown,
+            query_and_body: query_and_body_config(config, name, &second).map(|_| Precedence::Unknown),
+        })
+    }
+
+    let response = request_pairs(config, stats, client, &[pair(name, first), pair(name, &second)], reflections).await?;
+    let duplicates = page.precedence(&response)?;
+
+    let array_name = format!("{}[]", name);
+    let response = request_pairs(config, stats, client, &[pair(&array_name, first), pair(&array_name, &second)], reflections).await?;
+    let array = page.precedence(&response)?;
+
+    let query_and_body = match query_and_body_config(config, name, &second) {
+        Some(val) => {
+            let response = request_pairs(&val, stats, client, &[pair(name, first)], reflections).await?;
+            Some(page.precedence(&response)?)
+        },
+        None => None
+    };
+
+    Ok(Pollution {
+        duplicates,
+        array,
+        query_and_body,
+    })
+}
+
+fn pair(name: &str, value: &str) -> (String, String) {
+    (name.to_string(), value.to_string())
+}
+
+//the values are reflected more times than random ones when they are used
+fn precedence(text: &str, first: &str, second: &str, reflections: usize) -> Precedence {
+    let text = text.to_ascii_lowercase();
+
+    if text.contains(&format!("{},{}", first, second)) || text.contains(&format!("{}, {}", first, second)) {
+        return Precedence::Concatenated
+    }
+
+    match (text.matches(first).count() > reflections, text.matches(second).count() > reflections) {
+        (true, true) => Precedence::Both,
+        (true, false) => Precedence::First,
+        (false, true) => Precedence::Last,
+        (false, false) => Precedence::Ignored,
+    }
+}
+
+//a config that sends the parameter within the query while the second copy is already within the body
+fn query_and_body_config(config: &Config, name: &str, value: &str) -> Option<Config> {
+    match config.location() {
+        Location::Query => {
+            //the copy is added to the body the request already has in the same format
+            let mut body_config = location_config(config, Location::Body);
+            if config.body_type.contains("multipart") {
+                let boundary = set_multipart_content_type(&mut body_config.headers);
+                body_config.body = adjust_multipart_body(&config.body, &boundary);
+                body_config.body_type = config.body_type.clone();
+                body_config.parameter_template = multipart_parameter_template(&boundary);
+            }
+
+            Some(Config {
+                //bodies of GET requests are often ignored
+                method: if config.method == "GET" { String::from("POST") } else { config.method.clone() },
+                body: make_body(&body_config, &[pair(name, value)]),
+                headers: body_config.headers,
+                ..config.clone()
+            })
+        },
+        Location::Body => Some(Config {
+            url: add_query_injection(&config.url),
+            path: add_query_injection(&config.path),
+            body: make_body(config, &[(name.to_string(), value.to_string())]),
+            as_body: false,
+            parameter_template: String::from("%k=%v&"),
+            ..config.clone()
+        }),
+        _ => None
+    }
+}
+
+fn add_query_injection(url: &str) -> String {
+    if url.contains('?') {
+        url.to_string() + "&%s"
+    } else {
+        url.to_string() + "?%s"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn precedence_by_reflections() {
+        assert_eq!(precedence("<p>aaa,bbb</p>", "aaa", "bbb", 0), Precedence::Concatenated);
+        assert_eq!(precedence("<p>aaa, bbb</p>", "aaa", "bbb", 0), Precedence::Concatenated);
+        assert_eq!(precedence("<p>aaa</p><p>bbb</p>", "aaa", "bbb", 0), Precedence::Both);
+        assert_eq!(precedence("<p>aaa</p>", "aaa", "bbb", 0), Precedence::First);
+        assert_eq!(precedence("<p>bbb</p>", "aaa", "bbb", 0), Precedence::Last);
+        assert_eq!(precedence("<p></p>", "aaa", "bbb", 0), Precedence::Ignored);
+        //reflections of random values within the page don't count
+        assert_eq!(precedence("aaa bbb bbb", "aaa", "bbb", 1), Precedence::Last);
+        //the page is compared in lowercase
+        assert_eq!(precedence("<P>AAA</P>", "aaa", "bbb", 0), Precedence::First);
+    }
+
+    #[test]
+    fn query_and_body_without_body() {
+        let config = Config { method: String::from("GET"), ..Config::default() };
+
+        let config = query_and_body_config(&config, "sort", "asc").unwrap();
+        assert_eq!(config.method, "POST");
+        assert_eq!(config.body, "sort=asc&");
+        assert_eq!(config.headers.get("Content-Type").unwrap(), "application/x-www-form-urlencoded");
+        assert_eq!(config.location(), Location::Query);
+    }
+
+    #[test]
+    fn query_and_body_with_body() {
+        let config = Config {
+            method: String::from("PUT"),
+            body: String::from("a=1&b=2"),
+            ..Config::default()
+        };
+        let new = query_and_body_config(&config, "sort", "asc").unwrap();
+        assert_eq!(new.method, "PUT");
+        assert_eq!(new.body, "a=1&b=2&sort=asc&");
+
+        let config = Config {
+            method: String::from("POST"),
+            body: String::from(r#"{"a":"1"}"#),
+            headers: HashMap::from([(String::from("content-type"), String::from("application/json"))]),
+            ..Config::default()
+        };
+        let new = query_and_body_config(&config, "sort", "asc").unwrap();
+        assert_eq!(new.body, r#"{"a":"1", "sort":"asc"}"#);
+        assert_eq!(new.headers, config.headers);
+
+        let config = Config {
+            method: String::from("POST"),
+            body: String::from("--b\r\nContent-Disposition: form-data; name=\"a\"\r\n\r\n1\r\n--b--\r\n"),
+            body_type: String::from("multipart"),
+            headers: HashMap::from([(String::from("Content-Type"), String::from("multipart/form-data; boundary=b"))]),
+            ..Config::default()
+        };
+        let new = query_and_body_config(&config, "sort", "asc").unwrap();
+        assert_eq!(
+            new.body,
+            "--b\r\nContent-Disposition: form-data; name=\"a\"\r\n\r\n1\r\n--b\r\nContent-Disposition: form-data; name=\"sort\"\r\n\r\nasc\r\n--b--\r\n"
+        );
+    }
+
+    #[test]
+    fn query_and_body_within_body() {
+        let config = Config {
+            method: String::from("POST"),
+            url: String::from("https://example.com/"),
+            body: String::from("a=1&%s"),
+            parameter_template: String::from("%k=%v&"),
+            as_body: true,
+            ..Config::default()
+        };
+
+        let config = query_and_body_config(&config, "sort", "asc").unwrap();
+        assert_eq!(config.url, "https://example.com/?%s");
+        assert_eq!(config.body, "a=1&sort=asc&");
+        assert_eq!(config.location(), Location::Query);
+
+        let config = Config { cookies_discovery: true, ..Config::default() };
+        assert!(query_and_body_config(&config, "sort", "asc").is_none());
+    }
+}
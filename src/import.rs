@@ -0,0 +1,1092 @@
+use crate::{error::X8Error, structs::Config, utils::{multipart_parameter_template, parse_request, random_line}};
+use lazy_static::lazy_static;
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use regex::Regex;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::io::{self, Write};
+use url::Url;
+
+lazy_static! {
+    //the same chars curl leaves as they are in --data-urlencode
+    static ref URLENCODE: AsciiSet = NON_ALPHANUMERIC
+        .remove(b'-')
+        .remove(b'.')
+        .remove(b'_')
+        .remove(b'~');
+}
+
+//only requests that match these regexes are imported
+#[derive(Debug, Default)]
+pub struct Filter {
+    pub host: Option<Regex>,
+    pub path: Option<Regex>,
+}
+
+impl Filter {
+    fn is_match(&self, url: &Url) -> bool {
+        self.host.as_ref().is_none_or(|re| re.is_match(url.host_str().unwrap_or("")))
+            && self.path.as_ref().is_none_or(|re| re.is_match(url.path()))
+    }
+}
+
+//a single request from an export
+struct Entry {
+    url: Url,
+    request: String,
+    as_body: bool,
+    documented_parameters: Option<Vec<String>>,
+}
+
+//returns a config for every request from the Burp Suite xml export (Save items)
+pub fn from_burp(config: &Config, data: &str, filter: &Filter, custom_parameter_template: bool) -> Result<Vec<Config>, X8Error> {
+    let mut entries = Vec::new();
+
+    let mut skipped: usize = 0;
+
+    for (i, (_, item)) in xml_elements(data, "item").into_iter().enumerate() {
+        let url = match xml_elements(item, "url").first().map(|(_, x)| cdata(x).trim()) {
+            Some(url) => match Url::parse(url) {
+                Ok(val) => val,
+                Err(err) => {
+                    skipped += 1;
+                    warn_skipped(config, url, &err.to_string());
+                    continue
+                }
+            },
+            None => {
+                skipped += 1;
+                warn_skipped(config, &format!("item {}", i + 1), "unable to find the url");
+                continue
+            }
+        };
+
+        let (attributes, request) = match xml_elements(item, "request").first() {
+            Some(val) => *val,
+            None => {
+                skipped += 1;
+                warn_skipped(config, url.as_str(), "unable to find the request");
+                continue
+            }
+        };
+
+        let request = if attributes.contains(r#"base64="true""#) {
+            match base64::decode(cdata(request).trim()) {
+                Ok(val) => String::from_utf8_lossy(&val).to_string(),
+                Err(err) => {
+                    skipped += 1;
+                    warn_skipped(config, url.as_str(), &format!("unable to decode the request: {}", err));
+                    continue
+                }
+            }
+        } else {
+            cdata(request).to_string()
+        };
+
+        entries.push(Entry { url, request, as_body: config.as_body, documented_parameters: None });
+    }
+
+    to_configs(config, entries, skipped, filter, custom_parameter_template)
+}
+
+#[derive(Deserialize)]
+struct Har {
+    log: HarLog,
+}
+
+#[derive(Deserialize)]
+struct HarLog {
+    entries: Vec<HarEntry>,
+}
+
+#[derive(Deserialize)]
+struct HarEntry {
+    request: HarRequest,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct HarRequest {
+    method: String,
+    url: String,
+    #[serde(default)]
+    headers: Vec<HarHeader>,
+    post_data: Option<HarPostData>,
+}
+
+#[derive(Deserialize)]
+struct HarHeader {
+    name: String,
+    value: String,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct HarPostData {
+    #[serde(default)]
+    mime_type: String,
+    #[serde(default)]
+    text: String,
+}
+
+//returns a config for every request from the har file
+pub fn from_har(config: &Config, data: &str, filter: &Filter, custom_parameter_template: bool) -> Result<Vec<Config>, X8Error> {
+    let har: Har = serde_json::from_str(data).map_err(|err| X8Error::Import(err.to_string()))?;
+
+    let mut entries = Vec::new();
+    let mut skipped: usize = 0;
+
+    for entry in har.log.entries {
+        let har_request = entry.request;
+
+        let url = match Url::parse(&har_request.url) {
+            Ok(val) => val,
+            Err(err) => {
+                skipped += 1;
+                warn_skipped(config, &har_request.url, &err.to_string());
+                continue
+            }
+        };
+
+        let mut path = url.path().to_string();
+        if let Some(query) = url.query() {
+            path.push('?');
+            path.push_str(query);
+        }
+
+        let mut request = format!("{} {} HTTP/1.1\n", har_request.method, path);
+
+        //http2 requests have pseudo headers like :authority instead of the host header
+        if !har_request.headers.iter().any(|x| x.name.to_lowercase() == "host") {
+            request += &format!("Host: {}\n", host_with_port(&url));
+        }
+
+        for header in har_request.headers.iter().filter(|x| !x.name.starts_with(':')) {
+            request += &format!("{}: {}\n", header.name, header.value);
+        }
+
+        let post_data = har_request.post_data.unwrap_or_default();
+
+        if !post_data.mime_type.is_empty() && !har_request.headers.iter().any(|x| x.name.to_lowercase() == "content-type") {
+            request += &format!("Content-Type: {}\n", post_data.mime_type);
+        }
+
+        request.push('\n');
+        request.push_str(&post_data.text);
+
+        entries.push(Entry { url, request, as_body: config.as_body, documented_parameters: None });
+    }
+
+    to_configs(config, entries, skipped, filter, custom_parameter_template)
+}
+
+//returns a config for every operation from the OpenAPI 3 or Swagger 2 spec (json or yaml).
+//Documented parameters are sent within every request, so only undocumented ones are found
+pub fn from_openapi(config: &Config, data: &str, base_url: &str, filter: &Filter, custom_parameter_template: bool) -> Result<Vec<Config>, X8Error> {
+    let spec: Value = match serde_json::from_str(data) {
+        Ok(val) => val,
+        Err(_) => serde_yaml::from_str(data).map_err(|err| X8Error::Import(err.to_string()))?,
+    };
+
+    let base_url = if !base_url.is_empty() {
+        base_url.to_string()
+    } else {
+        spec_base_url(&spec).ok_or_else(|| X8Error::Import(
+            "the spec lacks an absolute server url. Specify it via --openapi-base".to_string()
+        ))?
+    };
+
+    let paths = spec["paths"]
+        .as_object()
+        .ok_or_else(|| X8Error::Import("the spec lacks paths".to_string()))?;
+
+    let mut entries = Vec::new();
+    let mut skipped: usize = 0;
+
+    for (path, item) in paths {
+        let item = resolve(&spec, item);
+
+        for method in ["get", "post", "put", "patch", "delete", "head"] {
+            let operation = match item.get(method) {
+                Some(val) => val,
+                None => continue
+            };
+
+            let mut path = path.to_owned();
+            let mut query: Vec<(String, String)> = Vec::new();
+            let mut headers: Vec<(String, String)> = Vec::new();
+            let mut cookies: Vec<String> = Vec::new();
+            let mut form: Vec<(String, String)> = Vec::new();
+            let mut is_form = false;
+            let mut is_multipart = false;
+            let mut json_body: Option<Value> = None;
+            let mut content_type = String::new();
+            let mut documented_parameters: Vec<String> = Vec::new();
+
+            //operation parameters override the path ones with the same name
+            let parameters = operation["parameters"].as_array().into_iter().flatten()
+                .chain(item["parameters"].as_array().into_iter().flatten())
+                .map(|x| resolve(&spec, x));
+
+            for parameter in parameters {
+                let name = match parameter["name"].as_str() {
+                    Some(val) => val.to_string(),
+                    None => continue
+                };
+                let location = parameter["in"].as_str().unwrap_or("");
+
+                if location != "body" && documented_parameters.contains(&name) {
+                    continue
+                }
+
+                //openapi 3 keeps types in the schema, swagger 2 within the parameter itself
+                let value = match parameter.get("example") {
+                    Some(val) => val.to_owned(),
+                    None => example_value(&spec, parameter.get("schema").unwrap_or(parameter), 0),
+                };
+
+                match location {
+                    "query" => query.push((name.clone(), value_to_string(&value))),
+                    "path" => path = path.replace(&format!("{{{}}}", name), &value_to_string(&value)),
+                    "header" => headers.push((name.clone(), value_to_string(&value))),
+                    "cookie" => cookies.push(format!("{}={}", name, value_to_string(&value))),
+                    "formData" => {
+                        is_form = true;
+                        //files can only be sent within multipart bodies
+                        is_multipart = operation["consumes"].as_array()
+                            .or_else(|| spec["consumes"].as_array())
+                            .is_some_and(|x| x.iter().any(|x| x.as_str() == Some("multipart/form-data")))
+                            || parameter["type"].as_str() == Some("file");
+                        form.push((name.clone(), value_to_string(&value)))
+                    },
+                    //swagger 2 body parameter describes the whole body
+                    "body" => {
+                        json_body = Some(value);
+                        continue
+                    },
+                    _ => continue
+                }
+
+                if location != "path" {
+                    documented_parameters.push(name);
+                }
+            }
+
+            //openapi 3 request body
+            if let Some(request_body) = operation.get("requestBody") {
+                let content = &resolve(&spec, request_body)["content"];
+
+                let media_type = ["json", "x-www-form-urlencoded", "form-data"]
+                    .iter()
+                    .find_map(|x| content.as_object()?.keys().find(|key| key.contains(x)));
+
+                if let Some(media_type) = media_type {
+                    let value = match content[media_type].get("example") {
+                        Some(val) => val.to_owned(),
+                        None => example_value(&spec, &content[media_type]["schema"], 0),
+                    };
+
+                    if media_type.contains("json") {
+                        json_body = Some(value);
+                    } else {
+                        is_form = true;
+                        is_multipart = media_type.contains("multipart");
+                        for (k, v) in value.as_object().into_iter().flatten() {
+                            form.push((k.to_owned(), value_to_string(v)));
+                        }
+                    }
+                }
+            }
+
+            let body = if let Some(json_body) = json_body {
+                //parameters can't be added to arrays or primitive values
+                let object = match json_body.as_object() {
+                    Some(val) => val,
+                    None => {
+                        skipped += 1;
+                        warn_skipped(config, &format!("{} {}", method.to_uppercase(), path), "the json body is not an object");
+                        continue
+                    }
+                };
+                content_type = String::from("application/json");
+                documented_parameters.extend(object.keys().map(|x| x.to_owned()));
+                json_body.to_string()
+            } else if is_multipart {
+                let boundary = String::from("----x8") + &random_line(16);
+                content_type = format!("multipart/form-data; boundary={}", boundary);
+                documented_parameters.extend(form.iter().map(|(k, _)| k.to_owned()));
+
+                let template = multipart_parameter_template(&boundary);
+                form.iter()
+                    .map(|(k, v)| template.replace("%k", k).replace("%v", v))
+                    .collect::<String>() + &format!("--{}--\r\n", boundary)
+            } else if is_form {
+                content_type = String::from("application/x-www-form-urlencoded");
+                documented_parameters.extend(form.iter().map(|(k, _)| k.to_owned()));
+                form.iter().map(|(k, v)| format!("{}={}", k, v)).collect::<Vec<String>>().join("&")
+            } else {
+                String::new()
+            };
+
+            let mut url = base_url.trim_end_matches('/').to_string() + &path;
+            if !query.is_empty() {
+                url.push('?');
+                url += &query.iter().map(|(k, v)| format!("{}={}", k, v)).collect::<Vec<String>>().join("&");
+            }
+
+            let url = match Url::parse(&url) {
+                Ok(val) => val,
+                Err(err) => {
+                    skipped += 1;
+                    warn_skipped(config, &format!("{} {}", method.to_uppercase(), url), &err.to_string());
+                    continue
+                }
+            };
+
+            let mut request = format!(
+                "{} {}{} HTTP/1.1\nHost: {}\n",
+                method.to_uppercase(),
+                url.path(),
+                url.query().map(|x| "?".to_owned() + x).unwrap_or_default(),
+                host_with_port(&url)
+            );
+
+            for (k, v) in headers {
+                request += &format!("{}: {}\n", k, v);
+            }
+            if !cookies.is_empty() {
+                request += &format!("Cookie: {}\n", cookies.join("; "));
+            }
+            if !content_type.is_empty() {
+                request += &format!("Content-Type: {}\n", content_type);
+            }
+
+            request.push('\n');
+            request.push_str(&body);
+
+            entries.push(Entry {
+                url,
+                request,
+                as_body: config.as_body || !content_type.is_empty(),
+                documented_parameters: Some(documented_parameters),
+            });
+        }
+    }
+
+    to_configs(config, entries, skipped, filter, custom_parameter_template)
+}
+
+//curl options that are followed by a value. Values of the other ones would be taken for the url
+const CURL_VALUE_FLAGS: &[&str] = &[
+    "--abstract-unix-socket", "--alt-svc", "--aws-sigv4", "--cacert", "--capath", "-E", "--cert", "--cert-type",
+    "--ciphers", "-K", "--config", "--connect-timeout", "--connect-to", "-C", "--continue-at", "-b", "--cookie",
+    "-c", "--cookie-jar", "--create-file-mode", "--crlfile", "--curves", "-d", "--data", "--data-ascii",
+    "--data-binary", "--data-raw", "--data-urlencode", "--delegation", "--dns-interface", "--dns-ipv4-addr",
+    "--dns-ipv6-addr", "--dns-servers", "--doh-url", "-D", "--dump-header", "--egd-file", "--engine",
+    "--etag-compare", "--etag-save", "--expect100-timeout", "-F", "--form", "--form-string", "--ftp-account",
+    "--ftp-alternative-to-user", "--ftp-method", "-P", "--ftp-port", "--ftp-ssl-ccc-mode",
+    "--happy-eyeballs-timeout-ms", "-H", "--header", "--hostpubmd5", "--hostpubsha256", "--hsts", "--interface",
+    "--json", "--keepalive-time", "--key", "--key-type", "--krb", "--libcurl", "--limit-rate", "--local-port",
+    "--login-options", "--mail-auth", "--mail-from", "--mail-rcpt", "--max-filesize", "--max-redirs", "-m",
+    "--max-time", "--netrc-file", "--noproxy", "--oauth2-bearer", "-o", "--output", "--output-dir",
+    "--parallel-max", "--pass", "--pinnedpubkey", "--preproxy", "--proto", "--proto-default", "--proto-redir",
+    "-x", "--proxy", "--proxy-cacert", "--proxy-capath", "--proxy-cert", "--proxy-cert-type", "--proxy-ciphers",
+    "--proxy-crlfile", "--proxy-header", "--proxy-key", "--proxy-key-type", "--proxy-pass",
+    "--proxy-pinnedpubkey", "--proxy-service-name", "--proxy-tls13-ciphers", "--proxy-tlsauthtype",
+    "--proxy-tlspassword", "--proxy-tlsuser", "-U", "--proxy-user", "--proxy1.0", "--pubkey", "-Q", "--quote",
+    "--random-file", "-r", "--range", "--rate", "-e", "--referer", "-X", "--request", "--request-target",
+    "--resolve", "--retry", "--retry-delay", "--retry-max-time", "--sasl-authzid", "--service-name", "--socks4",
+    "--socks4a", "--socks5", "--socks5-gssapi-service", "--socks5-hostname", "-Y", "--speed-limit", "-y",
+    "--speed-time", "--stderr", "-t", "--telnet-option", "--tftp-blksize", "-z", "--time-cond", "--tls-max",
+    "--tls13-ciphers", "--tlsauthtype", "--tlspassword", "--tlsuser", "--trace", "--trace-ascii",
+    "--unix-socket", "-T", "--upload-file", "--url", "--url-query", "-u", "--user", "-A", "--user-agent", "-w",
+    "--write-out", "--variable", "--ipfs-gateway", "--trace-config", "--ip-tos", "--vlan-priority", "--ech",
+];
+
+//returns a config from the curl command like the ones from "Copy as cURL" in browsers
+pub fn from_curl(config: &Config, command: &str, custom_parameter_template: bool) -> Result<Config, X8Error> {
+    let mut words = split_command(command)?.into_iter().peekable();
+
+    if words.peek().map(|x| x.as_str()) == Some("curl") {
+        words.next();
+    }
+
+    let mut method = String::new();
+    let mut url = String::new();
+    let mut headers: Vec<(String, String)> = Vec::new();
+    let mut data: Vec<String> = Vec::new();
+    let mut get = false;
+
+    while let Some(word) = words.next() {
+        let (flag, inline_value) = match word.split_once('=') {
+            //--flag=value form
+            Some((flag, value)) if word.starts_with("--") => (flag.to_string(), Some(value.to_string())),
+            //-XPOST form
+            _ => match word.get(..2) {
+                Some(flag) if word.len() > 2 && !word.starts_with("--") && CURL_VALUE_FLAGS.contains(&flag) => {
+                    (flag.to_string(), Some(word[2..].to_string()))
+                },
+                _ => (word.clone(), None),
+            },
+        };
+
+        let takes_value = CURL_VALUE_FLAGS.contains(&flag.as_str());
+
+        if !takes_value {
+            match flag.as_str() {
+                "-G" | "--get" => get = true,
+                _ if flag.starts_with('-') && flag.len() > 1 => (),
+                _ => url = word,
+            }
+            continue
+        }
+
+        let value = match inline_value.or_else(|| words.next()) {
+            Some(val) => val,
+            None => return Err(X8Error::Import(format!("{} lacks a value", flag))),
+        };
+
+        match flag.as_str() {
+            "-X" | "--request" => method = value,
+            "-H" | "--header" => {
+                if let Some((k, v)) = value.split_once(':') {
+                    headers.push((k.trim().to_string(), v.trim().to_string()));
+                }
+            },
+            //without = it is a file with cookies
+            "-b" | "--cookie" if value.contains('=') => headers.push((String::from("Cookie"), value)),
+            //curl strips new lines from the files, except for --data-binary
+            "-d" | "--data" | "--data-ascii" if value.starts_with('@') => {
+                data.push(read_data_file(&value[1..])?.replace(['\r', '\n'], ""))
+            },
+            "--data-binary" if value.starts_with('@') => data.push(read_data_file(&value[1..])?),
+            "-d" | "--data" | "--data-binary" | "--data-ascii" | "--data-raw" => data.push(value),
+            //content, =content, name=content, @file or name@file. The first = wins over @
+            "--data-urlencode" => {
+                let (name, content) = match value.find('=').or_else(|| value.find('@')) {
+                    Some(i) if value[i..].starts_with('@') => (&value[..i], read_data_file(&value[i + 1..])?),
+                    Some(i) => (&value[..i], value[i + 1..].to_string()),
+                    None => ("", value.clone()),
+                };
+
+                let content = utf8_percent_encode(&content, &URLENCODE).to_string();
+                data.push(if name.is_empty() { content } else { format!("{}={}", name, content) });
+            },
+            "--json" => {
+                data.push(value);
+                headers.push((String::from("Content-Type"), String::from("application/json")));
+                headers.push((String::from("Accept"), String::from("application/json")));
+            },
+            "-u" | "--user" => headers.push((
+                String::from("Authorization"),
+                format!("Basic {}", base64::encode(&value))
+            )),
+            "-A" | "--user-agent" => headers.push((String::from("User-Agent"), value)),
+            "-e" | "--referer" => headers.push((String::from("Referer"), value)),
+            "--url" => url = value,
+            _ => (),
+        }
+    }
+
+    if url.is_empty() {
+        return Err(X8Error::Import("the curl command lacks an url".to_string()))
+    }
+
+    //curl uses http by default
+    if !url.contains("://") {
+        url = "http://".to_owned() + &url;
+    }
+
+    let mut body = data.join("&");
+
+    //-G moves the data to the query
+    if get && !body.is_empty() {
+        url.push(if url.contains('?') { '&' } else { '?' });
+        url.push_str(&body);
+        body = String::new();
+    }
+
+    if method.is_empty() {
+        method = if body.is_empty() { String::from("GET") } else { String::from("POST") };
+    }
+
+    if !body.is_empty() && !headers.iter().any(|(k, _)| k.to_lowercase() == "content-type") {
+        headers.push((String::from("Content-Type"), String::from("application/x-www-form-urlencoded")));
+    }
+
+    let url = Url::parse(&url).map_err(|err| X8Error::InvalidUrl(url.clone(), err))?;
+
+    let mut request = format!(
+        "{} {}{} HTTP/1.1\n",
+        method,
+        url.path(),
+        url.query().map(|x| "?".to_owned() + x).unwrap_or_default()
+    );
+
+    if !headers.iter().any(|(k, _)| k.to_lowercase() == "host") {
+        request += &format!("Host: {}\n", host_with_port(&url));
+    }
+
+    for (k, v) in headers {
+        request += &format!("{}: {}\n", k, v);
+    }
+
+    request.push('\n');
+    request.push_str(&body);
+
+    parse_request(config.clone(), url.scheme(), &request, custom_parameter_template)
+}
+
+//returns the attributes and contents of every <tag> element.
+//CDATA sections are skipped while looking for the closing tag, so requests and responses containing </item> don't break the split
+fn xml_elements<'a>(data: &'a str, tag: &str) -> Vec<(&'a str, &'a str)> {
+    let open = format!("<{}", tag);
+    let close = format!("</{}>", tag);
+    let mut elements = Vec::new();
+    let mut rest = data;
+
+    while let Some(start) = find_outside_cdata(rest, &open) {
+        rest = &rest[start + open.len()..];
+
+        //<items> shouldn't match <item
+        if !rest.starts_with(|c: char| c == '>' || c.is_whitespace()) {
+            continue
+        }
+
+        let attributes_end = match rest.find('>') {
+            Some(val) => val,
+            None => break
+        };
+        let attributes = &rest[..attributes_end];
+        rest = &rest[attributes_end + 1..];
+
+        match find_outside_cdata(rest, &close) {
+            Some(end) => {
+                elements.push((attributes.trim(), &rest[..end]));
+                rest = &rest[end + close.len()..];
+            },
+            None => break
+        }
+    }
+
+    elements
+}
+
+fn find_outside_cdata(data: &str, pattern: &str) -> Option<usize> {
+    let mut offset = 0;
+
+    loop {
+        let rest = &data[offset..];
+        let found = rest.find(pattern)?;
+
+        match rest.find("<![CDATA[") {
+            Some(cdata_start) if cdata_start < found => {
+                offset += cdata_start + rest[cdata_start..].find("]]>")? + 3;
+            },
+            _ => return Some(offset + found),
+        }
+    }
+}
+
+//removes the CDATA wrapper if there is one
+fn cdata(content: &str) -> &str {
+    let trimmed = content.trim();
+
+    trimmed
+        .strip_prefix("<![CDATA[")
+        .and_then(|x| x.strip_suffix("]]>"))
+        .unwrap_or(content)
+}
+
+//the content of @file values. Reading from stdin (@-) isn't supported
+fn read_data_file(path: &str) -> Result<String, X8Error> {
+    if path == "-" {
+        return Err(X8Error::Import("reading data from stdin (@-) is not supported".to_string()))
+    }
+
+    std::fs::read_to_string(path).map_err(|err| X8Error::Import(format!("unable to read {}: {}", path, err)))
+}
+
+//splits the command into words the way shells do it
+fn split_command(command: &str) -> Result<Vec<String>, X8Error> {
+    let mut words: Vec<String> = Vec::new();
+    let mut word = String::new();
+    let mut in_word = false;
+    let mut chars = command.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                //line continuation
+                Some('\n') | Some('\r') => (),
+                Some(c) => {
+                    word.push(c);
+                    in_word = true;
+                },
+                None => (),
+            },
+            '\'' => {
+                in_word = true;
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => word.push(c),
+                        None => return Err(X8Error::Import("unterminated quote in the curl command".to_string())),
+                    }
+                }
+            },
+            '"' => {
+                in_word = true;
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(c) if ['"', '\\', '$', '`'].contains(&c) => word.push(c),
+                            Some('\n') => (),
+                            Some(c) => {
+                                word.push('\\');
+                                word.push(c);
+                            },
+                            None => return Err(X8Error::Import("unterminated quote in the curl command".to_string())),
+                        },
+                        Some(c) => word.push(c),
+                        None => return Err(X8Error::Import("unterminated quote in the curl command".to_string())),
+                    }
+                }
+            },
+            //$'...' strings with escape sequences, chrome uses them for bodies with special chars
+            '$' if chars.peek() == Some(&'\'') => {
+                chars.next();
+                in_word = true;
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some('\\') => match chars.next() {
+                            Some('n') => word.push('\n'),
+                            Some('r') => word.push('\r'),
+                            Some('t') => word.push('\t'),
+                            Some('x') => {
+                                let hex: String = (0..2).filter_map(|_| chars.next()).collect();
+                                match u8::from_str_radix(&hex, 16) {
+                                    Ok(val) => word.push(val as char),
+                                    Err(_) => return Err(X8Error::Import(format!("wrong escape sequence \\x{}", hex))),
+                                }
+                            },
+                            Some('u') => {
+                                let hex: String = (0..4).filter_map(|_| chars.next()).collect();
+                                match u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32) {
+                                    Some(val) => word.push(val),
+                                    None => return Err(X8Error::Import(format!("wrong escape sequence \\u{}", hex))),
+                                }
+                            },
+                            Some(c) => word.push(c),
+                            None => return Err(X8Error::Import("unterminated quote in the curl command".to_string())),
+                        },
+                        Some(c) => word.push(c),
+                        None => return Err(X8Error::Import("unterminated quote in the curl command".to_string())),
+                    }
+                }
+            },
+            c if c.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut word));
+                    in_word = false;
+                }
+            },
+            c => {
+                word.push(c);
+                in_word = true;
+            },
+        }
+    }
+
+    if in_word {
+        words.push(word);
+    }
+
+    Ok(words)
+}
+
+fn spec_base_url(spec: &Value) -> Option<String> {
+    //swagger 2
+    if let Some(host) = spec["host"].as_str() {
+        let scheme = spec["schemes"][0].as_str().unwrap_or("https");
+        return Some(format!("{}://{}{}", scheme, host, spec["basePath"].as_str().unwrap_or("")))
+    }
+
+    //openapi 3
+    let server = &spec["servers"][0];
+    let mut url = server["url"].as_str()?.to_string();
+
+    if let Some(variables) = server["variables"].as_object() {
+        for (k, v) in variables {
+            url = url.replace(&format!("{{{}}}", k), v["default"].as_str().unwrap_or(""));
+        }
+    }
+
+    if url.starts_with("http") {
+        Some(url)
+    } else {
+        None
+    }
+}
+
+//follows local references like #/components/schemas/Pet
+fn resolve<'a>(spec: &'a Value, value: &'a Value) -> &'a Value {
+    let mut value = value;
+
+    //limit the depth in case of circular references
+    for _ in 0..16 {
+        match value["$ref"].as_str().and_then(|x| x.strip_prefix('#')).and_then(|x| spec.pointer(x)) {
+            Some(val) => value = val,
+            None => break
+        }
+    }
+
+    value
+}
+
+fn example_value(spec: &Value, schema: &Value, depth: usize) -> Value {
+    let schema = resolve(spec, schema);
+
+    if let Some(val) = schema.get("example").or_else(|| schema.get("default")) {
+        return val.to_owned()
+    }
+    if let Some(val) = schema["enum"].get(0) {
+        return val.to_owned()
+    }
+
+    if depth > 8 {
+        return Value::Null
+    }
+
+    match schema["type"].as_str() {
+        Some("integer") | Some("number") => json!(1),
+        Some("boolean") => json!(true),
+        Some("array") => json!([example_value(spec, &schema["items"], depth + 1)]),
+        Some("object") | None if schema["properties"].is_object() => Value::Object(
+            schema["properties"]
+                .as_object()
+                .into_iter()
+                .flatten()
+                .map(|(k, v)| (k.to_owned(), example_value(spec, v, depth + 1)))
+                .collect()
+        ),
+        Some("object") => json!({}),
+        _ => json!("test"),
+    }
+}
+
+fn value_to_string(value: &Value) -> String {
+    match value {
+        Value::String(val) => val.to_owned(),
+        _ => value.to_string(),
+    }
+}
+
+fn host_with_port(url: &Url) -> String {
+    match url.port() {
+        Some(port) => format!("{}:{}", url.host_str().unwrap_or(""), port),
+        None => url.host_str().unwrap_or("").to_string(),
+    }
+}
+
+//one broken entry shouldn't prevent the rest from being imported
+fn warn_skipped(config: &Config, entry: &str, reason: &str) {
+    if config.verbose > 0 {
+        writeln!(io::stderr(), "[!] skipping {}: {}", entry, reason).ok();
+    }
+}
+
+//`skipped` is the amount of entries that were already dropped by the caller.
+//The import fails only when nothing could be imported
+fn to_configs(
+    config: &Config,
+    entries: Vec<Entry>,
+    mut skipped: usize,
+    filter: &Filter,
+    custom_parameter_template: bool
+) -> Result<Vec<Config>, X8Error> {
+    let mut configs: Vec<Config> = Vec::new();
+
+    for entry in entries.into_iter().filter(|x| filter.is_match(&x.url)) {
+        let target = match parse_request(
+            Config { as_body: entry.as_body, ..config.clone() },
+            entry.url.scheme(),
+            &entry.request,
+            custom_parameter_template
+        ) {
+            Ok(val) => val,
+            Err(err) => {
+                skipped += 1;
+                warn_skipped(config, entry.url.as_str(), &err.to_string());
+                continue
+            }
+        };
+        let target = Config { documented_parameters: entry.documented_parameters, ..target };
+
+        //proxy histories usually contain the same request many times
+        if !configs.iter().any(|x| x.method == target.method && x.initial_url == target.initial_url && x.body == target.body) {
+            configs.push(target);
+        }
+    }
+
+    if configs.is_empty() && skipped > 0 {
+        return Err(X8Error::Import(format!("none of the {} entries could be imported", skipped)))
+    }
+
+    Ok(configs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn har_skips_broken_urls() {
+        let har = r#"{"log": {"entries": [
+            {"request": {"method": "GET", "url": "http:// broken", "headers": []}},
+            {"request": {"method": "POST", "url": "https://example.com/api?id=1", "headers": [{"name": ":authority", "value": "example.com"}],
+                "postData": {"mimeType": "application/x-www-form-urlencoded", "text": "a=b"}}}
+        ]}}"#;
+
+        let configs = from_har(&Config::default(), har, &Filter::default(), false).unwrap();
+
+        assert_eq!(configs.len(), 1);
+        assert_eq!(configs[0].method, "POST");
+        assert_eq!(configs[0].initial_url, "https://example.com/api?id=1");
+        assert_eq!(configs[0].headers.get("Content-Type").map(|x| x.as_str()), Some("application/x-www-form-urlencoded"));
+    }
+
+    #[test]
+    fn har_fails_without_valid_entries() {
+        let har = r#"{"log": {"entries": [{"request": {"method": "GET", "url": "not a url", "headers": []}}]}}"#;
+
+        assert!(matches!(from_har(&Config::default(), har, &Filter::default(), false), Err(X8Error::Import(_))));
+    }
+
+    #[test]
+    fn burp_skips_broken_items() {
+        let burp = r#"<items>
+  <item><url><![CDATA[not a url]]></url><request base64="false"><![CDATA[GET / HTTP/1.1]]></request></item>
+  <item><url><![CDATA[https://example.com/a]]></url></item>
+  <item><url><![CDATA[https://example.com/b]]></url><request base64="true"><![CDATA[%%%]]></request></item>
+  <item><url><![CDATA[https://example.com/c]]></url><request base64="false"><![CDATA[broken]]></request></item>
+  <item><url><![CDATA[https://example.com/d]]></url><request base64="false"><![CDATA[GET /d HTTP/1.1
+Host: example.com
+
+]]></request></item>
+</items>"#;
+
+        let configs = from_burp(&Config::default(), burp, &Filter::default(), false).unwrap();
+
+        assert_eq!(configs.len(), 1);
+        assert_eq!(configs[0].initial_url, "https://example.com/d");
+
+        let broken = burp.replace("GET /d", "GET");
+        assert!(matches!(from_burp(&Config::default(), &broken, &Filter::default(), false), Err(X8Error::Import(_))));
+    }
+
+    #[test]
+    fn har_filters_hosts() {
+        let har = r#"{"log": {"entries": [
+            {"request": {"method": "GET", "url": "https://example.com/", "headers": []}},
+            {"request": {"method": "GET", "url": "https://other.com/", "headers": []}}
+        ]}}"#;
+        let filter = Filter { host: Some(Regex::new("^other").unwrap()), path: None };
+
+        let configs = from_har(&Config::default(), har, &filter, false).unwrap();
+
+        assert_eq!(configs.len(), 1);
+        assert_eq!(configs[0].host, "other.com");
+    }
+
+    #[test]
+    fn burp_items_with_cdata() {
+        let request = base64::encode("POST /login HTTP/1.1\nHost: example.com\n\nuser=a");
+        let burp = format!(r#"<?xml version="1.0"?>
+<items burpVersion="2023.1">
+  <item>
+    <url><![CDATA[https://example.com/search?q=1]]></url>
+    <request base64="false"><![CDATA[GET /search?q=1 HTTP/1.1
+Host: example.com
+
+]]></request>
+    <response base64="false"><![CDATA[HTTP/1.1 200 OK
+
+<xml></item><item><url>https://injected.com/</url></xml>]]></response>
+  </item>
+  <item>
+    <url><![CDATA[https://example.com/login]]></url>
+    <request base64="true"><![CDATA[{}]]></request>
+  </item>
+</items>"#, request);
+
+        let configs = from_burp(&Config::default(), &burp, &Filter::default(), false).unwrap();
+
+        assert_eq!(configs.len(), 2);
+        assert_eq!(configs[0].initial_url, "https://example.com/search?q=1");
+        assert_eq!(configs[1].method, "POST");
+        assert_eq!(configs[1].body, "user=a");
+    }
+
+    const SPEC: &str = r#"
+openapi: 3.0.0
+servers:
+  - url: https://api.example.com/v1
+paths:
+  /users/{id}:
+    parameters:
+      - {name: id, in: path, schema: {type: integer}}
+    get:
+      parameters:
+        - {name: fields, in: query, schema: {type: string, example: name}}
+  /users:
+    post:
+      requestBody:
+        content:
+          application/json:
+            schema: {$ref: '#/components/schemas/User'}
+  /users/bulk:
+    post:
+      requestBody:
+        content:
+          application/json:
+            schema: {type: array, items: {$ref: '#/components/schemas/User'}}
+  /avatar:
+    post:
+      requestBody:
+        content:
+          multipart/form-data:
+            schema:
+              type: object
+              properties:
+                file: {type: string, format: binary}
+components:
+  schemas:
+    User:
+      type: object
+      properties:
+        name: {type: string}
+        admin: {type: boolean}
+"#;
+
+    #[test]
+    fn openapi_operations() {
+        let config = Config { as_body: true, ..Config::default() };
+        let configs = from_openapi(&config, SPEC, "", &Filter::default(), false).unwrap();
+
+        //the array body is skipped
+        assert_eq!(configs.len(), 3);
+
+        assert_eq!(configs[0].method, "GET");
+        assert_eq!(configs[0].initial_url, "https://api.example.com/v1/users/1?fields=name");
+        assert_eq!(configs[0].documented_parameters, Some(vec![String::from("fields")]));
+
+        assert_eq!(configs[1].body, r#"{"name":"test","admin":true, %s}"#);
+        assert_eq!(configs[1].documented_parameters, Some(vec![String::from("name"), String::from("admin")]));
+    }
+
+    #[test]
+    fn openapi_skips_broken_operations() {
+        let spec = r#"{
+            "swagger": "2.0",
+            "host": "api.example.com",
+            "paths": {
+                "{port}/a": {"get": {"parameters": [{"name": "port", "in": "path", "type": "string", "example": ":99999"}]}},
+                "/b": {"get": {}}
+            }
+        }"#;
+
+        let configs = from_openapi(&Config::default(), spec, "", &Filter::default(), false).unwrap();
+
+        assert_eq!(configs.len(), 1);
+        assert_eq!(configs[0].initial_url, "https://api.example.com/b");
+
+        let broken = spec.replace(r#""/b": {"get": {}}"#, r#""/b": {}"#);
+        assert!(matches!(from_openapi(&Config::default(), &broken, "", &Filter::default(), false), Err(X8Error::Import(_))));
+    }
+
+    #[test]
+    fn openapi_multipart_body() {
+        let config = Config { as_body: true, ..Config::default() };
+        let configs = from_openapi(&config, SPEC, "", &Filter { host: None, path: Some(Regex::new("avatar").unwrap()) }, false).unwrap();
+
+        assert_eq!(configs.len(), 1);
+        assert!(configs[0].body_type.starts_with("multipart"));
+        assert!(configs[0].body.contains("Content-Disposition: form-data; name=\"file\"\r\n\r\ntest\r\n%s--"));
+        assert!(configs[0].headers.values().any(|x| x.starts_with("multipart/form-data; boundary=")));
+    }
+
+    #[test]
+    fn split_command_quotes() {
+        let words = split_command("curl 'http://a.com/?x=1' -H \"A: \\\"b\\\"\" \\\n --data-raw $'a=\\'b\\'\\n' c\\ d").unwrap();
+
+        assert_eq!(words, vec!["curl", "http://a.com/?x=1", "-H", "A: \"b\"", "--data-raw", "a='b'\n", "c d"]);
+        assert!(split_command("curl 'http://a.com").is_err());
+    }
+
+    #[test]
+    fn curl_joined_flags() {
+        let config = from_curl(&Config::default(), "curl -XPUT -HX-Api-Key:\\ 1 http://a.com/api", false).unwrap();
+        assert_eq!(config.method, "PUT");
+        assert_eq!(config.headers.get("X-Api-Key").map(|x| x.as_str()), Some("1"));
+
+        let config = from_curl(&Config::default(), "curl --request=PATCH --url=http://a.com/api --data-raw=a=b", false).unwrap();
+        assert_eq!(config.method, "PATCH");
+        assert_eq!(config.body, "a=b");
+    }
+
+    #[test]
+    fn curl_skips_option_values() {
+        let config = from_curl(
+            &Config::default(),
+            "curl -c jar.txt --max-redirs 3 -K cfg --connect-timeout 5 -w '%{http_code}' -sSL http://a.com/x -o out",
+            false
+        ).unwrap();
+
+        assert_eq!(config.initial_url, "http://a.com/x");
+    }
+
+    #[test]
+    fn curl_data_files() {
+        let path = std::env::temp_dir().join(format!("x8-curl-{}", random_line(8)));
+        std::fs::write(&path, "a=1\r\n&b=2\n").unwrap();
+        let path = path.to_str().unwrap();
+
+        let config = from_curl(&Config::default(), &format!("curl http://a.com/ -d @{}", path), false).unwrap();
+        assert_eq!(config.body, "a=1&b=2");
+
+        let config = from_curl(&Config::default(), &format!("curl http://a.com/ --data-urlencode q@{}", path), false).unwrap();
+        assert_eq!(config.body, "q=a%3D1%0D%0A%26b%3D2%0A");
+
+        //= goes first, so the @ is a part of the value
+        let config = from_curl(&Config::default(), "curl http://a.com/ --data-urlencode 'mail=a@b.com'", false).unwrap();
+        assert_eq!(config.body, "mail=a%40b.com");
+
+        std::fs::remove_file(path).ok();
+
+        assert!(from_curl(&Config::default(), "curl http://a.com/ -d @-", false).is_err());
+        assert!(from_curl(&Config::default(), "curl http://a.com/ --data-urlencode q@/nonexistent/x8", false).is_err());
+    }
+
+    #[test]
+    fn curl_repeated_cookies() {
+        let config = from_curl(
+            &Config::default(),
+            "curl http://a.com/ -H 'Cookie: a=1' -b 'b=2' --cookie c=3",
+            false
+        ).unwrap();
+
+        assert_eq!(config.headers.get("Cookie").map(|x| x.as_str()), Some("a=1; b=2; c=3"));
+    }
+
+    #[test]
+    fn curl_get_moves_data_to_query() {
+        let config = from_curl(&Config::default(), "curl -G a.com/search -d q=1 --data-urlencode 'w=a b'", false).unwrap();
+
+        assert_eq!(config.method, "GET");
+        assert_eq!(config.initial_url, "http://a.com/search?q=1&w=a%20b");
+        assert!(config.body.is_empty());
+    }
+}
@@ -1,6 +1,16 @@
 pub mod args;
+pub mod chars;
+pub mod error;
+pub mod graphql;
+pub mod hpp;
+pub mod import;
 pub mod logic;
+pub mod masks;
+pub mod reflections;
+pub mod report;
 pub mod requests;
+pub mod scanner;
 pub mod structs;
 pub mod utils;
+pub mod values;
 pub mod diff;
\ No newline at end of file
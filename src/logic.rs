@@ -1,7 +1,10 @@
 use crate::{
+    error::X8Error,
+    graphql::graphql_findings,
+    reflections::classify_reflections,
     requests::{random_request, request},
-    structs::{Config, ResponseData, DefaultResponse, Stable, FuturesData, Statistic},
-    utils::{compare, make_hashmap, random_line, generate_request},
+    structs::{Config, Finding, ResponseData, Reason, Stable, FuturesData, Statistic},
+    utils::{compare, is_header_change, make_hashmap, random_line, generate_request, generate_http_request},
 };
 use colored::*;
 use futures::stream::StreamExt;
@@ -14,7 +17,11 @@ use std::{
     io::{self, Write},
 };
 
+//the amount of sequential requests that have to be slow as well before a parameter is reported by the response time
+const TIMING_CONFIRMATIONS: usize = 2;
+
 //check parameters in a loop chunk by chunk
+#[allow(clippy::too_many_arguments)]
 pub async fn check_parameters(
     first: bool,
     config: &Config,
@@ -28,8 +35,8 @@ pub async fn check_parameters(
     max: usize,
     green_lines: &mut HashMap<String, usize>,
     remaining_params: &mut Vec<Vec<String>>,
-    found_params: &mut HashMap<String, String>,
-) {
+    found_params: &mut HashMap<String, Finding>,
+) -> Result<(), X8Error> {
     let all = params.len() / max;
     let mut count: usize = 0;
     let shared_diffs = Arc::new(Mutex::new(diffs));
@@ -43,17 +50,16 @@ pub async fn check_parameters(
             stats: Statistic{amount_of_requests: 0}
         };
 
-        let found_params: &HashMap<String, String> = &found_params;
+        let found_params: &HashMap<String, Finding> = found_params;
         let cloned_diffs = Arc::clone(&shared_diffs);
         let cloned_green_lines = Arc::clone(&shared_green_lines);
 
         async move {
 
-            let query = &make_hashmap(&chunk, config.value_size);
+            let query = &make_hashmap(chunk, config.value_size);
             let response =
                 request(config, &mut futures_data.stats, client, query, reflections_count)
-                    .await
-                    .unwrap_or(ResponseData::default());
+                    .await?;
 
             //progress bar
             if config.verbose > 0 && !config.disable_progress_bar { //TODO maybe use external library
@@ -68,17 +74,57 @@ pub async fn check_parameters(
                 io::stdout().flush().ok();
             }
 
+            //names from the GraphQL validation errors. The usual checks are still made
+            if !config.graphql.is_empty() {
+                match graphql_findings(config, chunk, &response.graphql_errors) {
+                    Some(findings) => {
+                        for (param, reason) in findings {
+                            if !found_params.contains_key(&param) && !futures_data.found_params.contains_key(&param) {
+                                if config.verbose > 0 {
+                                    writeln!(
+                                        io::stdout(),
+                                        "{}: {}",
+                                        &"graphql".bright_magenta(),
+                                        param
+                                    ).ok();
+                                }
+                                futures_data.found_params.insert(
+                                    param.to_owned(),
+                                    Finding::new(&param, reason, initial_response, &response, reflections_count, generate_http_request(config, query))
+                                );
+                            }
+                        }
+                    },
+                    None => {
+                        futures_data.remaining_params.append(&mut chunk.to_vec());
+                        return Ok(futures_data)
+                    }
+                }
+            }
+
             //try to find parameters with different number of reflections
             if stable.reflections && response.reflected_params.len() < 10 && response.reflected_params.len() != chunk.len() {
                 for param in response.reflected_params.keys() {
                     if !found_params.contains_key(param) {
-                        futures_data.found_params.insert(param.to_string(), String::from("Different amount of reflections"));
+                        futures_data.found_params.insert(
+                            param.to_string(),
+                            Finding {
+                                reflections: classify_reflections(&response, &query[param].replace("%random%_", "")),
+                                ..Finding::new(param, Reason::Reflects, initial_response, &response, reflections_count, generate_http_request(config, query))
+                            }
+                        );
                         if config.verbose > 0 {
                             writeln!(
                                 io::stdout(),
-                                "{}: {}",
+                                "{}: {} ({})",
                                 &"reflects".bright_blue(),
-                                param
+                                param,
+                                futures_data.found_params[param]
+                                    .reflections
+                                    .iter()
+                                    .map(|x| x.context.to_string())
+                                    .collect::<Vec<String>>()
+                                    .join(", ")
                             ).ok();
                         }
                     }
@@ -86,14 +132,14 @@ pub async fn check_parameters(
             //if the amount of reflected parameters == the amount of send parameters - that means that sth went wrong
             //so we are trying to find a parameter that caused that
             } else if stable.reflections && !response.reflected_params.is_empty() {
-                let mut not_reflected_one: &str = &"";
+                let mut not_reflected_one: &str = "";
 
                 //saves the number of occurencies for each number of reflections
                 //key: the number of reflections
                 let mut amount_of_reflections: HashMap<usize, usize> = HashMap::new();
 
-                for (_, v) in &response.reflected_params {
-                    if amount_of_reflections.contains_key(&v) {
+                for v in response.reflected_params.values() {
+                    if amount_of_reflections.contains_key(v) {
                         amount_of_reflections.insert(*v, amount_of_reflections[v] + 1);
                     } else {
                         amount_of_reflections.insert(*v, 1);
@@ -127,16 +173,55 @@ pub async fn check_parameters(
 
                 //if we found that parameter that caused others to reflect differently:
                 if !not_reflected_one.is_empty() {
-                    futures_data.found_params.insert(not_reflected_one.to_owned(), String::from("Causes other parameters to reflect different times"));
+                    futures_data.found_params.insert(
+                        not_reflected_one.to_owned(),
+                        Finding::new(not_reflected_one, Reason::CausesOtherReflections, initial_response, &response, reflections_count, generate_http_request(config, query))
+                    );
                 //in case we didn't find the unique parameter - check parameters till we find it or there is only one left
                 } else {
                     futures_data.remaining_params.append(&mut chunk.to_vec());
                 }
-                return futures_data
+                return Ok(futures_data)
             }
 
             if config.reflected_only {
-                return futures_data
+                return Ok(futures_data)
+            }
+
+            //parameters that make the server do more work. The chunk is split like with the other changes
+            //and is checked for them as well, because the page can change together with the time
+            let is_slow = config.timing && initial_response.code == response.code && stable.time.is_slow(response.time);
+
+            if is_slow && chunk.len() == 1 && !found_params.contains_key(&chunk[0]) && !futures_data.found_params.contains_key(&chunk[0]) {
+                //a single slow response can be caused by the network or the load from other requests
+                let mut confirmed = true;
+                for _ in 0..TIMING_CONFIRMATIONS {
+                    let response = request(config, &mut futures_data.stats, client, query, reflections_count).await?;
+                    if !stable.time.is_slow(response.time) {
+                        confirmed = false;
+                        break
+                    }
+                }
+
+                if confirmed {
+                    if config.verbose > 0 {
+                        writeln!(
+                            io::stdout(),
+                            "{}: time {:.0}ms -> {}ms",
+                            chunk[0],
+                            stable.time.mean,
+                            &response.time.as_millis().to_string().bright_yellow()
+                        ).ok();
+                    }
+                    futures_data.found_params.insert(
+                        chunk[0].to_owned(),
+                        Finding {
+                            time_before: stable.time.mean as u64,
+                            ..Finding::new(&chunk[0], Reason::ChangesTime, initial_response, &response, reflections_count, generate_http_request(config, query))
+                        }
+                    );
+                    return Ok(futures_data)
+                }
             }
 
             if initial_response.code == response.code {
@@ -144,29 +229,26 @@ pub async fn check_parameters(
                     let (_, new_diffs) = compare(
                         initial_response,
                         &response,
-                    );
-                    let mut diffs = cloned_diffs.lock();
-
+                    )?;
                     //check whether the new_diff has at least 1 unique diff
                     //and then check whether it was stored or not
-                    if !new_diffs.iter().all(|i| diffs.contains(i))  {
-                        //the next function with .await will never return if something is locked
-                        //so we need to unlock diffs firstly
-                        drop(diffs);
+                    //the lock is released right away because the next function with .await will never return if something is locked
+                    let has_unique_diff = {
+                        let diffs = cloned_diffs.lock();
+                        !new_diffs.iter().all(|i| diffs.contains(i))
+                    };
 
+                    if has_unique_diff {
                         let tmp_resp =
-                            random_request(&config, &mut futures_data.stats, &client, reflections_count, max)
-                            .await
-                            .unwrap_or(ResponseData::default());
-
-                        //lock it again
-                        diffs = cloned_diffs.lock();
+                            random_request(config, &mut futures_data.stats, client, reflections_count, max)
+                            .await?;
 
                         let (_, tmp_diffs) = compare(
                             initial_response,
                             &tmp_resp,
-                        );
+                        )?;
 
+                        let mut diffs = cloned_diffs.lock();
                         for diff in tmp_diffs {
                             if !diffs.iter().any(|i| i == &diff) {
                                 diffs.push(diff);
@@ -174,27 +256,30 @@ pub async fn check_parameters(
                         }
                     }
 
+                    let mut diffs = cloned_diffs.lock();
+
                     let mut green_lines = cloned_green_lines.lock();
 
                     for diff in new_diffs {
                         if !diffs.contains(&diff) {
                             if !config.save_responses.is_empty() {
                                 let mut output = generate_request(config, query);
-                                output += &("\n\n--- response ---\n\n".to_owned() + &response.text);
+                                output += &("\n\n--- response ---\n\n".to_owned() + &response.full_text());
 
                                 match std::fs::write(
                                     &(config.save_responses.clone() + "/" + &random_line(10)),
                                     output,
                                 ) {
                                     Ok(_) => (),
-                                    Err(err) => {
+                                    Err(err) if config.verbose > 0 => {
                                         writeln!(
-                                            io::stdout(),
+                                            io::stderr(),
                                             "Unable to write to {}/random_values due to {}",
                                             config.save_responses,
                                             err
                                         ).ok();
                                     }
+                                    Err(_) => (),
                                 }
                             }
 
@@ -225,17 +310,31 @@ pub async fn check_parameters(
                             }
 
                             if chunk.len() == 1 && !found_params.contains_key(&chunk[0]) && !futures_data.found_params.contains_key(&chunk[0]) {
-                                if config.verbose > 0 {
-                                    writeln!(
-                                        io::stdout(),
-                                        "{}: page {} -> {} ({})",
-                                        chunk[0],
-                                        initial_response.text.len(),
-                                        &response.text.len().to_string().bright_yellow(),
-                                        &diff
-                                    ).ok();
-                                }
-                                futures_data.found_params.insert(chunk[0].to_owned(), format!("Changes page: {} -> {}", initial_response.text.len(), response.text.len()));
+                                let finding = if is_header_change(&diff) {
+                                    if config.verbose > 0 {
+                                        writeln!(io::stdout(), "{}: {}", chunk[0], &diff.bright_yellow()).ok();
+                                    }
+
+                                    Finding {
+                                        header_change: Some(diff.to_string()),
+                                        ..Finding::new(&chunk[0], Reason::ChangesHeaders, initial_response, &response, reflections_count, generate_http_request(config, query))
+                                    }
+                                } else {
+                                    if config.verbose > 0 {
+                                        writeln!(
+                                            io::stdout(),
+                                            "{}: page {} -> {} ({})",
+                                            chunk[0],
+                                            initial_response.text.len(),
+                                            &response.text.len().to_string().bright_yellow(),
+                                            &diff
+                                        ).ok();
+                                    }
+
+                                    Finding::new(&chunk[0], Reason::ChangesPage, initial_response, &response, reflections_count, generate_http_request(config, query))
+                                };
+
+                                futures_data.found_params.insert(chunk[0].to_owned(), finding);
                                 break;
                             } else {
                                 futures_data.remaining_params.append(&mut chunk.to_vec());
@@ -254,23 +353,27 @@ pub async fn check_parameters(
                         &response.code.to_string().bright_yellow()
                     ).ok();
                 }
-                futures_data.found_params.insert(chunk[0].to_owned(), format!("Changes response code: {} -> {}", initial_response.code, response.code));
+                futures_data.found_params.insert(
+                    chunk[0].to_owned(),
+                    Finding::new(&chunk[0], Reason::ChangesCode, initial_response, &response, reflections_count, generate_http_request(config, query))
+                );
             } else {
                 if !config.save_responses.is_empty() {
                     let filename = random_line(10);
                     let mut output = generate_request(config, query);
-                    output += &("\n\n--- response ---\n\n".to_owned() + &response.text);
+                    output += &("\n\n--- response ---\n\n".to_owned() + &response.full_text());
 
                     match std::fs::write(&(config.save_responses.clone() + "/" + &filename), output) {
                         Ok(_) => (),
-                        Err(err) => {
+                        Err(err) if config.verbose > 0 => {
                             writeln!(
-                                io::stdout(),
+                                io::stderr(),
                                 "Unable to write to {}/random_values due to {}",
                                 config.save_responses,
                                 err
                             ).ok();
                         }
+                        Err(_) => (),
                     }
 
                     if config.verbose > 1 {
@@ -293,62 +396,59 @@ pub async fn check_parameters(
 
                 futures_data.remaining_params.append(&mut chunk.to_vec());
 
-                let mut green_lines = cloned_green_lines.lock();
-
                 //to prevent loops when ip got banned or server broke
-                match green_lines.get(&response.code.to_string()) {
-                    Some(val) => {
-                        let n_val = *val;
-                        green_lines.insert(response.code.to_string(), n_val + 1);
-                        if n_val > 50 {
-                            drop(green_lines);
+                let code_count = {
+                    let mut green_lines = cloned_green_lines.lock();
+                    let code_count = green_lines.get(&response.code.to_string()).copied();
+                    green_lines.insert(response.code.to_string(), code_count.map_or(0, |x| x + 1));
+                    code_count
+                };
 
-                            let mut random_params: Vec<String> = Vec::new();
+                if matches!(code_count, Some(n_val) if n_val > 50) {
+                    let mut random_params: Vec<String> = Vec::new();
 
-                            for _ in 0..max {
-                                random_params.push(random_line(config.value_size));
-                            }
+                    for _ in 0..max {
+                        random_params.push(random_line(config.value_size));
+                    }
 
-                            let query = make_hashmap(
-                                &random_params[..],
-                                config.value_size,
-                            );
+                    let query = make_hashmap(
+                        &random_params[..],
+                        config.value_size,
+                    );
 
-                            let check_response =
-                                request(config, &mut futures_data.stats, client, &query, 0)
-                                    .await
-                                    .unwrap_or(ResponseData::default());
+                    let check_response =
+                        request(config, &mut futures_data.stats, client, &query, 0)
+                            .await?;
 
-                            if check_response.code != initial_response.code {
-                                writeln!(
-                                    io::stderr(),
-                                    "[!] {} the page became unstable (code)",
-                                    &config.url
-                                ).ok();
-                                std::process::exit(1) //TODO return error instead
-                            } else {
-                                let mut green_lines = cloned_green_lines.lock();
-                                green_lines.insert(response.code.to_string(), 0);
-                            }
-                        }
-                    }
-                    _ => {
+                    if check_response.code != initial_response.code {
+                        return Err(X8Error::UnstableCode(config.url.clone()))
+                    } else {
+                        let mut green_lines = cloned_green_lines.lock();
                         green_lines.insert(response.code.to_string(), 0);
                     }
                 }
             }
-            futures_data
+
+            //the slow chunk didn't change the page, so it is split only because of the time
+            if is_slow && chunk.len() > 1 && futures_data.remaining_params.is_empty() {
+                futures_data.remaining_params.append(&mut chunk.to_vec());
+            }
+
+            Ok(futures_data)
         }
     }))
     .buffer_unordered(config.concurrency)
-    .collect::<Vec<FuturesData>>()
+    .collect::<Vec<Result<FuturesData, X8Error>>>()
     .await;
 
     for instance in futures_data {
+        let instance = instance?;
         for (k, v) in instance.found_params {
             found_params.insert(k, v);
         }
         remaining_params.push(instance.remaining_params);
         stats.merge(instance.stats);
     }
-}
\ No newline at end of file
+
+    Ok(())
+}
@@ -0,0 +1,174 @@
+use crate::diff::changed_lines;
+use lazy_static::lazy_static;
+use regex::{Captures, Regex};
+
+const MASK: &str = "%x8-mask%";
+
+lazy_static! {
+    //already masked values are words as well
+    static ref RE_WORD: Regex = Regex::new(&format!(r"{}|\w+", MASK)).unwrap();
+}
+
+//chars of dynamic values like tokens, timestamps and ids
+const VALUE_CHARS: &str = r#"[^\s"'<>&,;]"#;
+
+//the amount of words before the dynamic value that are used to find it
+const CONTEXT_WORDS: usize = 2;
+
+//the maximum amount of dynamic values within a single line
+const MAX_LINE_MASKS: usize = 8;
+
+//replaces the first group of every mask with the placeholder. Masks without groups replace the whole match
+pub fn apply_masks(masks: &[Regex], text: &str) -> String {
+    let mut text = text.to_string();
+
+    for mask in masks {
+        text = mask.replace_all(&text, |caps: &Captures| {
+            let whole = caps.get(0).unwrap();
+
+            match caps.get(1) {
+                Some(group) => [
+                    &whole.as_str()[..group.start() - whole.start()],
+                    MASK,
+                    &whole.as_str()[group.end() - whole.start()..],
+                ].concat(),
+                None => MASK.to_string()
+            }
+        }).to_string();
+    }
+
+    text
+}
+
+//creates masks for the parts that differ between the responses to the same request.
+//Every dynamic value is found by the words before it, e.g. 'csrf" value="' or '"nonce": "'
+pub fn learn_masks(texts: &[String]) -> Vec<Regex> {
+    let mut masks: Vec<Regex> = Vec::new();
+
+    let first = match texts.first() {
+        Some(val) => val,
+        None => return masks
+    };
+
+    for text in texts.iter().skip(1) {
+        let pairs = match changed_lines(&apply_masks(&masks, first), &apply_masks(&masks, text)) {
+            Ok(val) => val,
+            Err(_) => continue
+        };
+
+        for (mut line1, mut line2) in pairs {
+            for _ in 0..MAX_LINE_MASKS {
+                if line1 == line2 {
+                    break
+                }
+
+                let mask = match line_mask(&line1, &line2) {
+                    Some(val) => val,
+                    None => break
+                };
+
+                line1 = apply_masks(std::slice::from_ref(&mask), &line1);
+                line2 = apply_masks(std::slice::from_ref(&mask), &line2);
+
+                if !masks.iter().any(|x| x.as_str() == mask.as_str()) {
+                    masks.push(mask);
+                }
+            }
+        }
+    }
+
+    masks
+}
+
+//creates a mask for the first difference between the lines
+fn line_mask(line1: &str, line2: &str) -> Option<Regex> {
+    let is_value_char = |c: char| !c.is_whitespace() && !"\"'<>&,;".contains(c);
+
+    let common = line1
+        .char_indices()
+        .zip(line2.chars())
+        .find(|((_, c1), c2)| c1 != c2)
+        .map(|((i, _), _)| i)
+        .unwrap_or_else(|| line1.len().min(line2.len()));
+
+    //the value starts at the beginning of the word it differs in
+    let start = line1[..common]
+        .char_indices()
+        .rev()
+        .take_while(|(_, c)| is_value_char(*c))
+        .last()
+        .map(|(i, _)| i)
+        .unwrap_or(common);
+
+    let words = RE_WORD.find_iter(&line1[..start]).collect::<Vec<_>>();
+    if words.is_empty() {
+        return None
+    }
+
+    let context = &line1[words[words.len().saturating_sub(CONTEXT_WORDS)].start()..start];
+
+    //the mask is useless when it doesn't match one of the lines
+    let mask = Regex::new(&format!("{}({}+)", regex::escape(context), VALUE_CHARS)).ok()?;
+    if !mask.is_match(line1) || !mask.is_match(line2) {
+        return None
+    }
+
+    let masked = apply_masks(std::slice::from_ref(&mask), line1);
+    if masked == line1 {
+        return None
+    }
+
+    Some(mask)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn page(token: &str, time: &str) -> String {
+        format!(
+            "<html>\n<body>\n<p>static</p>\n<input name=\"csrf\" value=\"{}\">\n<span>served at {} by node</span>\n<p>footer</p>\n</body>\n</html>",
+            token, time
+        )
+    }
+
+    #[test]
+    fn masks_dynamic_tokens() {
+        let texts = vec![page("a8f3k2", "1700000001"), page("Zq9x", "1700000002"), page("m2-b_7", "1700000003")];
+
+        let masks = learn_masks(&texts);
+
+        assert!(!masks.is_empty());
+        assert_eq!(apply_masks(&masks, &texts[0]), apply_masks(&masks, &texts[1]));
+        assert_eq!(apply_masks(&masks, &texts[0]), apply_masks(&masks, &page("other", "1800000000")));
+        assert!(apply_masks(&masks, &texts[0]).contains(&format!("value=\"{}\"", MASK)));
+    }
+
+    #[test]
+    fn keeps_parameter_changes() {
+        let texts = vec![page("a8f3k2", "1"), page("Zq9x", "2")];
+
+        let masks = learn_masks(&texts);
+        let changed = page("a8f3k2", "1").replace("footer", "debug mode");
+
+        assert_ne!(apply_masks(&masks, &texts[0]), apply_masks(&masks, &changed));
+    }
+
+    #[test]
+    fn identical_pages_have_no_masks() {
+        let texts = vec![page("a", "1"), page("a", "1")];
+
+        assert!(learn_masks(&texts).is_empty());
+        assert!(learn_masks(&[]).is_empty());
+    }
+
+    #[test]
+    fn apply_masks_groups() {
+        let masks = vec![Regex::new(r"id=(\d+)").unwrap(), Regex::new(r"\d{4}-\d{2}-\d{2}").unwrap()];
+
+        assert_eq!(
+            apply_masks(&masks, "id=123 at 2024-01-02"),
+            format!("id={} at {}", MASK, MASK)
+        );
+    }
+}
@@ -0,0 +1,176 @@
+use crate::structs::{Reflection, ReflectionContext, ResponseData};
+
+//the amount of chars around the reflection within the snippet
+const SNIPPET_CONTEXT: usize = 20;
+
+//reflections after this amount aren't classified
+const MAX_REFLECTIONS: usize = 10;
+
+//the amount of reflections of the value within the body and the header values
+pub fn count_reflections(body: &str, headers: &[(String, String)], value: &str) -> usize {
+    let value = value.to_ascii_lowercase();
+
+    body.to_ascii_lowercase().matches(&value).count()
+        + headers
+            .iter()
+            .map(|(_, v)| v.to_ascii_lowercase().matches(&value).count())
+            .sum::<usize>()
+}
+
+//finds where the value was reflected, e.g. within a quoted attribute or a script block
+pub fn classify_reflections(response: &ResponseData, value: &str) -> Vec<Reflection> {
+    let value = value.to_ascii_lowercase();
+    let mut reflections: Vec<Reflection> = Vec::new();
+
+    if value.is_empty() {
+        return reflections
+    }
+
+    for (name, header_value) in response.headers.iter() {
+        for (position, _) in header_value.to_ascii_lowercase().match_indices(&value) {
+            reflections.push(Reflection {
+                context: ReflectionContext::Header,
+                snippet: format!("{}: {}", name, snippet(header_value, position, value.len())),
+            });
+        }
+    }
+
+    //json is often sent with other content types
+    let is_json = response
+        .headers
+        .iter()
+        .any(|(k, v)| k == "content-type" && v.contains("json"))
+        || serde_json::from_str::<serde_json::Value>(&response.text).is_ok_and(|x| x.is_object() || x.is_array());
+
+    let body = response.text.to_ascii_lowercase();
+
+    for (position, _) in body.match_indices(&value) {
+        let context = if is_json {
+            ReflectionContext::JsonString
+        } else {
+            html_context(&body[..position])
+        };
+
+        reflections.push(Reflection {
+            context,
+            snippet: snippet(&response.text, position, value.len()),
+        });
+    }
+
+    reflections.truncate(MAX_REFLECTIONS);
+    reflections
+}
+
+//the context is defined by the last unclosed comment, script or tag before the reflection
+fn html_context(before: &str) -> ReflectionContext {
+    let is_open = |open: &str, close: &str| match before.rfind(open) {
+        Some(start) => before.rfind(close).is_none_or(|end| end < start),
+        None => false,
+    };
+
+    if is_open("<!--", "-->") {
+        return ReflectionContext::Comment
+    }
+
+    if is_open("<script", "</script") {
+        return ReflectionContext::Script
+    }
+
+    if !is_open("<", ">") {
+        return ReflectionContext::HtmlText
+    }
+
+    let tag = &before[before.rfind('<').unwrap_or(0)..];
+
+    //quotes that are still open at the reflection
+    let mut quote: Option<char> = None;
+    for c in tag.chars() {
+        match quote {
+            Some(val) if c == val => quote = None,
+            None if c == '"' || c == '\'' => quote = Some(c),
+            _ => (),
+        }
+    }
+
+    if quote.is_some() {
+        ReflectionContext::QuotedAttribute
+    } else {
+        ReflectionContext::UnquotedAttribute
+    }
+}
+
+//the reflection with a few chars around it within a single line
+fn snippet(text: &str, position: usize, len: usize) -> String {
+    let start = text[..position]
+        .char_indices()
+        .rev()
+        .take(SNIPPET_CONTEXT)
+        .last()
+        .map_or(position, |(i, _)| i);
+
+    let end = text[position + len..]
+        .char_indices()
+        .nth(SNIPPET_CONTEXT)
+        .map_or(text.len(), |(i, _)| position + len + i);
+
+    text[start..end].split_whitespace().collect::<Vec<&str>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structs::DefaultResponse;
+
+    #[test]
+    fn html_context_text_and_attributes() {
+        assert_eq!(html_context("<p>hello "), ReflectionContext::HtmlText);
+        assert_eq!(html_context("<p>1 < 2 > 1, "), ReflectionContext::HtmlText);
+        assert_eq!(html_context("<input value=\""), ReflectionContext::QuotedAttribute);
+        assert_eq!(html_context("<input value='a\" "), ReflectionContext::QuotedAttribute);
+        assert_eq!(html_context("<input value="), ReflectionContext::UnquotedAttribute);
+        //closed quotes don't matter
+        assert_eq!(html_context("<input type=\"text\" value="), ReflectionContext::UnquotedAttribute);
+    }
+
+    #[test]
+    fn html_context_comments_and_scripts() {
+        assert_eq!(html_context("<!-- <a href=\""), ReflectionContext::Comment);
+        assert_eq!(html_context("<!-- a --><p>"), ReflectionContext::HtmlText);
+        assert_eq!(html_context("<script>var a = \"<b>"), ReflectionContext::Script);
+        assert_eq!(html_context("<script>var a;</script><p>"), ReflectionContext::HtmlText);
+    }
+
+    #[test]
+    fn classify_headers_and_json() {
+        let response = ResponseData {
+            text: String::from(r#"{"q": "Abc123"}"#),
+            headers: vec![(String::from("location"), String::from("/search?q=abc123"))],
+            ..ResponseData::default()
+        };
+
+        let reflections = classify_reflections(&response, "abc123");
+        assert_eq!(reflections.len(), 2);
+        assert_eq!(reflections[0].context, ReflectionContext::Header);
+        assert_eq!(reflections[0].snippet, "location: /search?q=abc123");
+        assert_eq!(reflections[1].context, ReflectionContext::JsonString);
+        //the snippet keeps the case of the page
+        assert_eq!(reflections[1].snippet, r#"{"q": "Abc123"}"#);
+    }
+
+    #[test]
+    fn classify_limits() {
+        let response = ResponseData { text: "<p>abc</p>\n".repeat(20), ..ResponseData::default() };
+
+        let reflections = classify_reflections(&response, "abc");
+        assert_eq!(reflections.len(), MAX_REFLECTIONS);
+        assert_eq!(reflections[0].context, ReflectionContext::HtmlText);
+        assert!(classify_reflections(&response, "").is_empty());
+    }
+
+    #[test]
+    fn snippet_around_reflection() {
+        let text = format!("{}<p>\n  abc </p>{}", "x".repeat(30), "y".repeat(30));
+        let position = text.find("abc").unwrap();
+        assert_eq!(snippet(&text, position, 3), format!("{}<p> abc </p>{}", "x".repeat(14), "y".repeat(15)));
+    }
+}
@@ -0,0 +1,376 @@
+use crate::{
+    diff::unified_diff,
+    error::X8Error,
+    structs::{Config, Finding, Reason, ScanResult},
+    utils::escape_xml,
+};
+use serde_json::json;
+
+//formats that describe all the targets at once
+pub fn is_report(format: &str) -> bool {
+    matches!(format, "sarif" | "junit" | "html")
+}
+
+pub fn create_report(format: &str, results: &[(&Config, Result<ScanResult, X8Error>)]) -> String {
+    match format {
+        "sarif" => sarif(results),
+        "html" => html(results),
+        _ => junit(results),
+    }
+}
+
+//sorted findings, so reports don't change between runs because of the hashmap order
+fn sorted_findings(result: &ScanResult) -> Vec<&Finding> {
+    let mut findings: Vec<&Finding> = result.found_params.values().collect();
+    findings.sort_by(|a, b| a.name.cmp(&b.name));
+    findings
+}
+
+//SARIF 2.1.0 log with a result for every found parameter
+pub fn sarif(results: &[(&Config, Result<ScanResult, X8Error>)]) -> String {
+    let mut reasons: Vec<Reason> = Vec::new();
+    let mut sarif_results = Vec::new();
+    let mut notifications = Vec::new();
+
+    for (config, result) in results {
+        let result = match result {
+            Ok(val) => val,
+            Err(err) => {
+                notifications.push(json!({
+                    "level": "error",
+                    "message": { "text": err.to_string() },
+                }));
+                continue
+            }
+        };
+
+        for finding in sorted_findings(result) {
+            if !reasons.contains(&finding.reason) {
+                reasons.push(finding.reason);
+            }
+
+            sarif_results.push(json!({
+                "ruleId": finding.reason.id(),
+                "level": "warning",
+                "message": {
+                    "text": format!("{} {}: {}", config.method, finding.name, finding),
+                },
+                "locations": [{
+                    "physicalLocation": {
+                        "artifactLocation": { "uri": config.initial_url },
+                    },
+                }],
+                "properties": finding,
+            }));
+        }
+    }
+
+    let rules = reasons
+        .iter()
+        .map(|reason| json!({
+            "id": reason.id(),
+            "name": reason.id(),
+            "shortDescription": { "text": reason.title() },
+        }))
+        .collect::<Vec<serde_json::Value>>();
+
+    let log = json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "x8",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "informationUri": env!("CARGO_PKG_HOMEPAGE"),
+                    "rules": rules,
+                },
+            },
+            "invocations": [{
+                "executionSuccessful": notifications.is_empty(),
+                "toolExecutionNotifications": notifications,
+            }],
+            "results": sarif_results,
+        }],
+    });
+
+    //serializing of a json value can't fail
+    serde_json::to_string_pretty(&log).unwrap() + "\n"
+}
+
+//JUnit xml where every target is a test case.
+//A test case fails when parameters that aren't in --expected-parameters are found
+pub fn junit(results: &[(&Config, Result<ScanResult, X8Error>)]) -> String {
+    let mut testcases = String::new();
+    let mut failures: usize = 0;
+    let mut errors: usize = 0;
+
+    for (config, result) in results {
+        testcases.push_str(&format!(
+            "    <testcase classname=\"{}\" name=\"{}\">\n",
+            escape_xml(&config.method),
+            escape_xml(&config.initial_url)
+        ));
+
+        match result {
+            Ok(result) => {
+                let unexpected = sorted_findings(result)
+                    .into_iter()
+                    .filter(|finding| !config.expected_parameters.contains(&finding.name))
+                    .collect::<Vec<&Finding>>();
+
+                if !unexpected.is_empty() {
+                    failures += 1;
+
+                    testcases.push_str(&format!(
+                        "      <failure type=\"unexpected_parameters\" message=\"{} unexpected parameter(s) found\">",
+                        unexpected.len()
+                    ));
+                    for finding in unexpected {
+                        testcases.push_str(&escape_xml(&format!("{}: {}\n", finding.name, finding)));
+                    }
+                    testcases.push_str("</failure>\n");
+                }
+
+                testcases.push_str(&format!(
+                    "      <system-out>{}</system-out>\n",
+                    escape_xml(&format!("Amount of requests: {}", result.amount_of_requests))
+                ));
+            },
+            Err(err) => {
+                errors += 1;
+                testcases.push_str(&format!(
+                    "      <error message=\"{}\"/>\n",
+                    escape_xml(&err.to_string())
+                ));
+            }
+        }
+
+        testcases.push_str("    </testcase>\n");
+    }
+
+    format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+        <testsuites name=\"x8\" tests=\"{tests}\" failures=\"{failures}\" errors=\"{errors}\">\n  \
+        <testsuite name=\"x8\" tests=\"{tests}\" failures=\"{failures}\" errors=\"{errors}\">\n\
+        {testcases}  \
+        </testsuite>\n\
+        </testsuites>\n",
+        tests = results.len(),
+        failures = failures,
+        errors = errors,
+        testcases = testcases
+    )
+}
+
+const HTML_STYLE: &str = "
+body { font-family: sans-serif; margin: 2em; color: #222; }
+h2 { border-bottom: 1px solid #ccc; padding-bottom: 0.2em; word-break: break-all; }
+table { border-collapse: collapse; margin-bottom: 1em; }
+td, th { border: 1px solid #ccc; padding: 0.3em 0.8em; text-align: left; }
+details { margin: 0.5em 0; border: 1px solid #ddd; border-radius: 4px; padding: 0.5em; }
+summary { cursor: pointer; font-weight: bold; }
+pre { background: #f6f8fa; padding: 0.8em; overflow-x: auto; white-space: pre-wrap; word-break: break-all; }
+.error { color: #b00020; }
+.add { background: #e6ffec; }
+.del { background: #ffebe9; }
+.hunk { color: #0550ae; }
+";
+
+//standalone html page with the found parameters and the diffs of their responses
+pub fn html(results: &[(&Config, Result<ScanResult, X8Error>)]) -> String {
+    let mut body = String::new();
+
+    for (config, result) in results {
+        body.push_str(&format!(
+            "<h2>{} {}</h2>\n",
+            escape_xml(&config.method),
+            escape_xml(&config.initial_url)
+        ));
+
+        let result = match result {
+            Ok(val) => val,
+            Err(err) => {
+                body.push_str(&format!("<p class=\"error\">{}</p>\n", escape_xml(&err.to_string())));
+                continue
+            }
+        };
+
+        body.push_str(&format!(
+            "<table>\n\
+            <tr><th>Code</th><td>{}</td></tr>\n\
+            <tr><th>Response Len</th><td>{}</td></tr>\n\
+            <tr><th>Reflections</th><td>{}</td></tr>\n\
+            <tr><th>Max</th><td>{}</td></tr>\n\
+            <tr><th>Amount of requests</th><td>{}</td></tr>\n\
+            </table>\n",
+            result.initial_code,
+            result.initial_size,
+            result.reflections_count,
+            result.max,
+            result.amount_of_requests
+        ));
+
+        if let Some(documented_parameters) = &config.documented_parameters {
+            body.push_str(&format!(
+                "<p>Documented parameters: {}</p>\n<p>Found parameters are missing from the spec</p>\n",
+                escape_xml(&documented_parameters.join(", "))
+            ));
+        }
+
+        if result.found_params.is_empty() {
+            body.push_str("<p>No parameters were found</p>\n");
+            continue
+        }
+
+        for finding in sorted_findings(result) {
+            body.push_str(&format!(
+                "<details>\n<summary>{}: {}</summary>\n<h4>Request</h4>\n<pre>{}</pre>\n<h4>Diff</h4>\n<pre>{}</pre>\n</details>\n",
+                escape_xml(&finding.name),
+                escape_xml(&finding.to_string()),
+                escape_xml(&finding.request),
+                html_diff(&result.initial_response, &finding.response)
+            ));
+        }
+    }
+
+    format!(
+        "<!DOCTYPE html>\n\
+        <html>\n\
+        <head>\n\
+        <meta charset=\"utf-8\">\n\
+        <title>x8 report</title>\n\
+        <style>{}</style>\n\
+        </head>\n\
+        <body>\n\
+        <h1>x8 report</h1>\n\
+        {}\
+        </body>\n\
+        </html>\n",
+        HTML_STYLE,
+        body
+    )
+}
+
+fn html_diff(initial_response: &str, response: &str) -> String {
+    let lines = match unified_diff(initial_response, response, 3) {
+        Ok(val) => val,
+        Err(err) => return escape_xml(&err.to_string()),
+    };
+
+    if lines.is_empty() {
+        return String::from("The response body is the same");
+    }
+
+    lines
+        .iter()
+        .map(|line| {
+            let class = if line.starts_with("@@") {
+                "hunk"
+            } else if line.starts_with('+') {
+                "add"
+            } else if line.starts_with('-') {
+                "del"
+            } else {
+                ""
+            };
+            format!("<span class=\"{}\">{}</span>", class, escape_xml(line))
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structs::{DefaultResponse, ResponseData};
+    use std::collections::HashMap;
+
+    fn config() -> Config {
+        Config {
+            method: String::from("GET"),
+            initial_url: String::from("https://example.com/?a=1&b=\"2\""),
+            expected_parameters: vec![String::from("id")],
+            ..Config::default()
+        }
+    }
+
+    fn result(names: &[&str]) -> ScanResult {
+        let initial_response = ResponseData { text: String::from("<p>hello</p>"), code: 200, ..ResponseData::default() };
+        let response = ResponseData { text: String::from("<p>hello & <b>debug</b></p>"), code: 200, ..ResponseData::default() };
+
+        ScanResult {
+            found_params: names
+                .iter()
+                .map(|name| (
+                    name.to_string(),
+                    Finding::new(name, Reason::ChangesPage, &initial_response, &response, 0, format!("GET /?{}=1 HTTP/1.1", name))
+                ))
+                .collect::<HashMap<String, Finding>>(),
+            amount_of_requests: 10,
+            initial_code: 200,
+            initial_size: initial_response.text.len(),
+            initial_response: initial_response.full_text(),
+            reflections_count: 0,
+            max: 128,
+        }
+    }
+
+    #[test]
+    fn sarif_results() {
+        let config = config();
+        let results = vec![
+            (&config, Ok(result(&["debug", "<x>"]))),
+            (&config, Err(X8Error::UnstableCode(String::from("https://example.com/\"")))),
+        ];
+
+        let log: serde_json::Value = serde_json::from_str(&sarif(&results)).unwrap();
+        let run = &log["runs"][0];
+
+        assert_eq!(run["results"].as_array().unwrap().len(), 2);
+        assert_eq!(run["results"][0]["message"]["text"], "GET <x>: Changes page: 12 -> 27");
+        assert_eq!(run["results"][0]["locations"][0]["physicalLocation"]["artifactLocation"]["uri"], config.initial_url);
+        assert_eq!(run["tool"]["driver"]["rules"].as_array().unwrap().len(), 1);
+        assert_eq!(run["invocations"][0]["executionSuccessful"], false);
+        assert_eq!(
+            run["invocations"][0]["toolExecutionNotifications"][0]["message"]["text"],
+            "https://example.com/\" the page is not stable (code)"
+        );
+    }
+
+    #[test]
+    fn junit_escaping() {
+        let config = config();
+        let results = vec![
+            (&config, Ok(result(&["id"]))),
+            (&config, Ok(result(&["id", "<x>"]))),
+            (&config, Err(X8Error::Unreachable(String::from("https://example.com/?a&b")))),
+        ];
+
+        let report = junit(&results);
+
+        assert!(report.contains(r#"<testsuites name="x8" tests="3" failures="1" errors="1">"#));
+        assert!(report.contains(r#"name="https://example.com/?a=1&amp;b=&quot;2&quot;""#));
+        assert!(report.contains("1 unexpected parameter(s) found\">&lt;x&gt;: Changes page: 12 -&gt; 27\n</failure>"));
+        assert!(report.contains(r#"<error message="unable to reach https://example.com/?a&amp;b"/>"#));
+        assert!(!report.contains("<x>"));
+    }
+
+    #[test]
+    fn html_escaping() {
+        let config = config();
+        let results = vec![
+            (&config, Ok(result(&["<x>"]))),
+            (&config, Err(X8Error::Unreachable(String::from("https://example.com/<script>")))),
+        ];
+
+        let report = html(&results);
+
+        assert!(report.contains("<h2>GET https://example.com/?a=1&amp;b=&quot;2&quot;</h2>"));
+        assert!(report.contains("<summary>&lt;x&gt;: Changes page: 12 -&gt; 27</summary>"));
+        assert!(report.contains("<pre>GET /?&lt;x&gt;=1 HTTP/1.1</pre>"));
+        assert!(report.contains("<span class=\"add\">+&lt;p&gt;hello &amp; &lt;b&gt;debug&lt;/b&gt;&lt;/p&gt;</span>"));
+        assert!(report.contains("<p class=\"error\">unable to reach https://example.com/&lt;script&gt;</p>"));
+        assert!(!report.contains("<x>") && !report.contains("<script>"));
+    }
+}
@@ -1,11 +1,16 @@
 use crate::{
-    structs::{Config, ResponseData, Stable, Statistic, DefaultResponse},
-    utils::{compare, beautify_html, beautify_json, make_body, make_query, make_header_value, make_hashmap, fix_headers, random_line},
+    error::X8Error,
+    graphql::split_errors,
+    masks::apply_masks,
+    reflections::count_reflections,
+    structs::{Config, GraphqlErrors, Location, ResponseData, Stable, Statistic, Timing},
+    utils::{compare, beautify_html, beautify_json, beautify_xml, make_injection, make_hashmap, fix_headers, random_line},
 };
 use colored::*;
 use reqwest::Client;
+use tokio::sync::SemaphorePermit;
 use std::{
-    time::Duration,
+    time::{Duration, Instant},
     collections::{BTreeMap, HashMap},
     io::{self, Write},
 };
@@ -21,18 +26,19 @@ pub async fn empty_reqs(
     count: usize,
     client: &Client,
     max: usize,
-) -> (Vec<String>, Stable) {
+) -> Result<(Vec<String>, Stable), X8Error> {
     let mut stable = Stable {
         body: true,
         reflections: true,
+        time: Timing::default(),
     };
     let mut diffs: Vec<String> = Vec::new();
+    let mut times: Vec<Duration> = vec![initial_response.time];
 
     for i in 0..count {
         let response =
             random_request(config, stats, client, reflections_count, max)
-                .await
-                .unwrap_or(ResponseData::default());
+                .await?;
 
         //progress bar
         if config.verbose > 0 && !config.disable_progress_bar {
@@ -47,23 +53,19 @@ pub async fn empty_reqs(
         }
 
         if response.text.len() > MAX_PAGE_SIZE && !config.force {
-            writeln!(io::stderr(), "[!] {} the page is too huge", &config.url).ok(); //TODO return error
-            std::process::exit(1)
+            return Err(X8Error::PageTooLarge(config.url.clone()))
         }
 
         if !response.reflected_params.is_empty() {
             stable.reflections = false;
         }
 
-        let (is_code_the_same, new_diffs) = compare(initial_response, &response);
+        times.push(response.time);
+
+        let (is_code_the_same, new_diffs) = compare(initial_response, &response)?;
 
         if !is_code_the_same {
-            writeln!(
-                io::stderr(),
-                "[!] {} the page is not stable (code)",
-                &config.url
-            ).ok();
-            std::process::exit(1)
+            return Err(X8Error::UnstableCode(config.url.clone()))
         }
 
         for diff in new_diffs {
@@ -75,10 +77,12 @@ pub async fn empty_reqs(
 
     let response =
         random_request(config, stats, client, reflections_count, max)
-            .await
-            .unwrap_or(ResponseData::default());//TODO replace with
+            .await?;
+
+    times.push(response.time);
+    stable.time = Timing::new(&times);
 
-    for diff in compare(initial_response, &response).1 {
+    for diff in compare(initial_response, &response)?.1 {
         if !diffs.iter().any(|i| i == &diff) {
             if config.verbose > 0 {
                 writeln!(
@@ -88,10 +92,31 @@ pub async fn empty_reqs(
                 ).ok();
             }
             stable.body = false;
-            return (diffs, stable);
+            return Ok((diffs, stable));
         }
     }
-    (diffs, stable)
+    Ok((diffs, stable))
+}
+
+//builds a client with the same settings for both usual and replay requests.
+//The cookie store is disabled in the cookie discovery mode, otherwise cookies from responses could replace the injected ones
+pub fn create_client(proxy: &str, follow_redirects: bool, cookie_store: bool) -> Result<Client, X8Error> {
+    let mut client = Client::builder()
+        //.resolve("localhost", "127.0.0.1".parse().unwrap())
+        .danger_accept_invalid_certs(true)
+        .timeout(Duration::from_secs(60))
+        .http1_title_case_headers()
+        .cookie_store(cookie_store)
+        .use_rustls_tls();
+
+    if !proxy.is_empty() {
+        client = client.proxy(reqwest::Proxy::all(proxy)?);
+    }
+    if !follow_redirects {
+        client = client.redirect(reqwest::redirect::Policy::none());
+    }
+
+    Ok(client.build()?)
 }
 
 //calls request() with random parameters
@@ -101,11 +126,11 @@ pub async fn random_request(
     client: &Client,
     reflections: usize,
     max: usize,
-) -> Option<ResponseData> {
+) -> Result<ResponseData, X8Error> {
     request(
-        &config,
+        config,
         stats,
-        &client,
+        client,
         &make_hashmap(
             &(0..max).map(|_| random_line(config.value_size*2)).collect::<Vec<String>>(),
             config.value_size,
@@ -117,16 +142,18 @@ pub async fn random_request(
 fn create_request(
     config: &Config,
     query: String,
-    hashmap_query: &HashMap<String, String>,
+    hashmap_query: &[(String, String)],
     client: &Client
-) -> reqwest::RequestBuilder {
+) -> Result<reqwest::RequestBuilder, X8Error> {
     let url: String = if config.url.contains("%s") {
         config.url.replace("%s", &query)
     } else {
         config.url.clone()
     };
 
-    let mut client = if config.as_body {
+    let location = config.location();
+
+    let mut client = if location == Location::Body {
         match config.method.as_str() {
             "GET" => client.get(url).body(query.clone()),
             "POST" => client.post(url).body(query.clone()),
@@ -134,10 +161,7 @@ fn create_request(
             "PATCH" => client.patch(url).body(query.clone()),
             "DELETE" => client.delete(url).body(query.clone()),
             "HEAD" => client.head(url).body(query.clone()),
-            _ => {
-                writeln!(io::stderr(), "Method is not supported").ok();
-                std::process::exit(1); //TODO return error
-            },
+            _ => return Err(X8Error::UnsupportedMethod(config.method.clone())),
         }
     } else {
         match config.method.as_str() {
@@ -147,34 +171,31 @@ fn create_request(
             "PATCH" => client.patch(url),
             "DELETE" => client.delete(url),
             "HEAD" => client.head(url),
-            _ => {
-                writeln!(io::stderr(), "Method is not supported").ok();
-                std::process::exit(1);
-            }
+            _ => return Err(X8Error::UnsupportedMethod(config.method.clone())),
         }
     };
 
-    client = if config.as_body && !config.disable_cachebuster {
+    client = if location == Location::Body && !config.disable_cachebuster {
         client.query(&[(random_line(config.value_size), random_line(config.value_size))])
     } else {
         client
     };
 
-    client = if !config.as_body && !config.body.is_empty() {
+    client = if location != Location::Body && !config.body.is_empty() {
         client.body(config.body.clone())
     } else {
         client
     };
 
     for (key, value) in config.headers.iter() {
-        if value.contains("%s") && config.within_headers {
+        if value.contains("%s") && matches!(location, Location::HeaderValue | Location::Cookies) {
             client = client.header(key, value.replace("%s", &query).replace("{{random}}", &random_line(config.value_size)));
         } else {
             client = client.header(key, value.replace("{{random}}", &random_line(config.value_size)));
         };
     }
 
-    if config.headers_discovery && !config.within_headers {
+    if location == Location::Headers {
         for (key, value) in hashmap_query.iter() {
 
             client = match fix_headers(key) {
@@ -184,7 +205,7 @@ fn create_request(
         }
     }
 
-    client
+    Ok(client)
 }
 
 pub async fn request(
@@ -193,75 +214,86 @@ pub async fn request(
     client: &Client,
     initial_query: &HashMap<String, String>,
     reflections: usize,
-) -> Option<ResponseData> {
-    let mut hashmap_query: HashMap<String, String> = HashMap::with_capacity(initial_query.len());
-    for (k, v) in initial_query.iter() {
-        hashmap_query.insert(k.to_string(), v.replace("%random%_", ""));
+) -> Result<ResponseData, X8Error> {
+    let pairs: Vec<(String, String)> = initial_query
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+
+    request_pairs(config, stats, client, &pairs, reflections).await
+}
+
+//waits till the amount of requests in flight is below the limit
+async fn acquire(config: &Config) -> Option<SemaphorePermit<'_>> {
+    match &config.requests_limit {
+        Some(limit) => limit.acquire().await.ok(),
+        None => None
     }
+}
 
-    let query: String = if !hashmap_query.is_empty() {
-        if config.as_body {
-            make_body(&config, &hashmap_query)
-        } else if config.within_headers {
-            make_header_value(&config, &hashmap_query)
-        } else if config.headers_discovery {
-            String::new()
-        } else {
-            make_query(&config, &hashmap_query)
-        }
-    } else {
-        String::new()
-    };
+//the same as request() but the parameters are sent in the given order and can be duplicated
+pub async fn request_pairs(
+    config: &Config,
+    stats: &mut Statistic,
+    client: &Client,
+    initial_query: &[(String, String)],
+    reflections: usize,
+) -> Result<ResponseData, X8Error> {
+    let pairs: Vec<(String, String)> = initial_query
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.replace("%random%_", "")))
+        .collect();
+
+    let query: String = make_injection(config, &pairs);
 
-    std::thread::sleep(config.delay);
+    tokio::time::sleep(config.delay).await;
 
     let url: &str = &config.url;
 
+    //the permit is held till the body is read
+    let permit = acquire(config).await;
+
     stats.amount_of_requests += 1;
-    let res = match create_request(config, query, &hashmap_query, client).send().await {
+    let start = Instant::now();
+    let res = match create_request(config, query, &pairs, client)?.send().await {
         Ok(val) => val,
         Err(_) => {
             //Try to make a random request instead
-            let mut random_query: HashMap<String, String> = HashMap::with_capacity(hashmap_query.len());
-            for (k, v) in make_hashmap(
-                &(0..hashmap_query.len()).map(|_| random_line(config.value_size)).collect::<Vec<String>>(),
-                config.value_size,
-            ) {
-                random_query.insert(k.to_string(), v.replace("%random%_", ""));
-            }
-            let random_query: String = if !random_query.is_empty() {
-                if config.as_body {
-                    make_body(&config, &random_query)
-                } else if config.headers_discovery {
-                    make_header_value(&config, &random_query)
-                } else {
-                    make_query(&config, &random_query)
-                }
-            } else {
-                String::new()
-            };
+            let random_query: Vec<(String, String)> = (0..pairs.len())
+                .map(|_| (random_line(config.value_size), random_line(config.value_size)))
+                .collect();
+            let random_query: String = make_injection(config, &random_query);
 
             stats.amount_of_requests += 1;
-            match create_request(config, random_query.clone(), &hashmap_query, client).send().await {
-                Ok(_) => return Some(ResponseData {
+            match create_request(config, random_query.clone(), &pairs, client)?.send().await {
+                Ok(_) => return Ok(ResponseData {
                                     text: String::new(),
                                     code: 0,
+                                    headers: Vec::new(),
                                     reflected_params: HashMap::new(),
+                                    graphql_errors: GraphqlErrors::default(),
+                                    time: start.elapsed(),
                                 }),
                 Err(err) => {
-                    writeln!(io::stderr(), "[!] {} {:?}", url, err).ok();
-                    writeln!(io::stderr(), "[~] error at the {} observed. Wait 50 sec and repeat.", config.url).ok();
-                    std::thread::sleep(Duration::from_secs(50));
-                    match create_request(config, random_query, &hashmap_query, client).send().await {
-                        Ok(_) => return Some(ResponseData {
+                    if config.verbose > 0 {
+                        writeln!(io::stderr(), "[!] {} {:?}", url, err).ok();
+                        writeln!(io::stderr(), "[~] error at the {} observed. Wait 50 sec and repeat.", config.url).ok();
+                    }
+                    //other targets can use the permit while this one waits
+                    drop(permit);
+                    tokio::time::sleep(Duration::from_secs(50)).await;
+                    let _permit = acquire(config).await;
+
+                    match create_request(config, random_query, &pairs, client)?.send().await {
+                        Ok(_) => return Ok(ResponseData {
                             text: String::new(),
                             code: 0,
+                            headers: Vec::new(),
                             reflected_params: HashMap::new(),
+                            graphql_errors: GraphqlErrors::default(),
+                            time: start.elapsed(),
                         }),
-                        Err(_) => {
-                            writeln!(io::stderr(), "[!] unable to reach {}", config.url).ok();
-                            std::process::exit(1);
-                        }
+                        Err(_) => return Err(X8Error::Unreachable(config.url.clone()))
                     }
                 }
             }
@@ -270,55 +302,73 @@ pub async fn request(
 
     let code = res.status().as_u16();
     let mut headers: BTreeMap<String, String> = BTreeMap::new();
+    //every value of the headers that can be repeated, like Set-Cookie
+    let mut header_list: Vec<(String, String)> = Vec::new();
     for (key, value) in res.headers().iter() {
         headers.insert(
             key.as_str().to_string(),
             value.to_str().unwrap_or("").to_string(),
         );
+        header_list.push((key.as_str().to_string(), value.to_str().unwrap_or("").to_string()));
     }
+    header_list.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let mut graphql_errors = GraphqlErrors::default();
 
     let body = match res.text().await {
         Ok(val) => {
+            let val = if !config.graphql.is_empty() {
+                let (val, errors) = split_errors(&val, &config.graphql);
+                graphql_errors = errors;
+                val
+            } else {
+                val
+            };
+
             if config.disable_response_correction {
                 val
             } else if config.is_json
-                || (headers.get("content-type").is_some()
-                    && headers.get("content-type").unwrap().as_str().contains(&"json"))
+                || (headers.contains_key("content-type")
+                    && headers.get("content-type").unwrap().as_str().contains("json"))
             {
                 beautify_json(&val)
-            } else if headers.get("content-type").is_some()
-                && headers.get("content-type").unwrap().as_str().contains(&"html")
+            } else if headers.contains_key("content-type")
+                && headers.get("content-type").unwrap().as_str().contains("html")
             {
                 beautify_html(&val)
+            } else if headers.contains_key("content-type")
+                && headers.get("content-type").unwrap().as_str().contains("xml")
+            {
+                beautify_xml(&val)
             } else {
                 val
             }
         }
         Err(_) => String::new(),
     };
+    let time = start.elapsed();
 
     let mut reflected_params: HashMap<String, usize> = HashMap::new();
 
     for (key, value) in initial_query.iter() {
-        let number_of_reflections = body.to_ascii_lowercase().matches(&value.replace("%random%_", "").as_str()).count();
+        let number_of_reflections = count_reflections(&body, &header_list, &value.replace("%random%_", ""));
         if value.contains("%random%_") && number_of_reflections as usize != reflections {
             reflected_params.insert(key.to_string(), number_of_reflections);
         }
     }
 
-    let mut text = String::new();
-    for (key, value) in headers.iter() {
-        text.push_str(&key);
-        text.push_str(&": ");
-        text.push_str(&value);
-        text.push_str(&"\n");
-    }
-    text.push_str(&"\n\n");
-    text.push_str(&body);
+    let text = if config.masks.is_empty() {
+        body
+    } else {
+        apply_masks(&config.masks, &body)
+    };
 
-    Some(ResponseData {
+    Ok(ResponseData {
         text,
+        headers: header_list,
         code,
         reflected_params,
+        graphql_errors,
+        time,
     })
 }
\ No newline at end of file
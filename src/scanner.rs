@@ -0,0 +1,678 @@
+use crate::{
+    chars::probe_special_chars,
+    error::X8Error,
+    graphql::{is_graphql_name, query_names},
+    hpp::check_pollution,
+    logic::check_parameters,
+    masks::{apply_masks, learn_masks},
+    reflections::count_reflections,
+    requests::{create_client, empty_reqs, random_request, request},
+    structs::{Config, Finding, Reason, ScanResult, Statistic, ValueType},
+    utils::{
+        compare, find_parents, location_config, heuristic, is_cookie_name, is_dotted_preset, is_xml_name, make_hashmap,
+        preset_params, random_line
+    },
+    values::enumerate_values,
+};
+use colored::*;
+use reqwest::Client;
+use std::{
+    collections::{HashMap, HashSet},
+    io::{self, Write},
+};
+
+//the amount of identical requests the dynamic parts of the page are learned from
+const MASK_REQUESTS: usize = 2;
+
+//collects everything needed to scan a single target
+pub struct ScanBuilder {
+    config: Config,
+    client: Option<Client>,
+    replay_client: Option<Client>,
+    params: Vec<String>,
+}
+
+impl ScanBuilder {
+    pub fn new(config: Config) -> Self {
+        Self {
+            config,
+            client: None,
+            replay_client: None,
+            params: Vec::new(),
+        }
+    }
+
+    //use an already built client instead of creating a new one from the config
+    pub fn client(mut self, client: Client) -> Self {
+        self.client = Some(client);
+        self
+    }
+
+    pub fn replay_client(mut self, client: Client) -> Self {
+        self.replay_client = Some(client);
+        self
+    }
+
+    //parameters to check. Possible parameters from the page are added automatically
+    pub fn params(mut self, params: Vec<String>) -> Self {
+        self.params = params;
+        self
+    }
+
+    pub fn build(self) -> Result<Scanner, X8Error> {
+        let client = match self.client {
+            Some(val) => val,
+            None => create_client(&self.config.proxy, self.config.follow_redirects, self.config.cookie_store())?
+        };
+
+        let replay_client = match self.replay_client {
+            Some(val) => Some(val),
+            None if !self.config.replay_proxy.is_empty() => Some(
+                create_client(&self.config.replay_proxy, self.config.follow_redirects, self.config.cookie_store())?
+            ),
+            None => None
+        };
+
+        Ok(Scanner {
+            config: self.config,
+            client,
+            replay_client,
+            params: self.params,
+        })
+    }
+}
+
+//runs the whole discovery process against a single target.
+//Nothing is printed unless config.verbose > 0
+pub struct Scanner {
+    config: Config,
+    client: Client,
+    replay_client: Option<Client>,
+    params: Vec<String>,
+}
+
+impl Scanner {
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    pub async fn scan(&self) -> Result<ScanResult, X8Error> {
+        if self.config.locations.is_empty() {
+            return self.scan_config(&self.config).await
+        }
+
+        //every location is scanned separately and the results are merged.
+        //A location that can't be scanned doesn't stop the rest of them
+        let mut merged: Option<ScanResult> = None;
+        let mut last_error: Option<X8Error> = None;
+
+        for location in self.config.locations.iter() {
+            match self.scan_config(&location_config(&self.config, *location)).await {
+                Ok(result) => match merged.as_mut() {
+                    Some(val) => val.merge(result),
+                    None => merged = Some(result),
+                },
+                Err(err) => {
+                    if self.config.verbose > 0 {
+                        writeln!(io::stdout(), "[!] {}: {}", location, err).ok();
+                    }
+                    last_error = Some(err);
+                }
+            }
+        }
+
+        match (merged, last_error) {
+            (Some(result), _) => Ok(result),
+            (None, Some(err)) => Err(err),
+            //locations aren't empty, so there is either a result or an error
+            (None, None) => unreachable!(),
+        }
+    }
+
+    async fn scan_config(&self, config: &Config) -> Result<ScanResult, X8Error> {
+        let client = &self.client;
+        let mut params = self.params.clone();
+
+        let mut stats = Statistic{amount_of_requests: 0};
+        let mut max = config.max;
+
+        //saves false-positive diffs
+        let mut green_lines: HashMap<String, usize> = HashMap::new();
+
+        if config.verbose > 0 {
+            writeln!(
+                io::stdout(),
+                " _________  __ ___     _____\n|{} {}",
+                &config.method.blue(),
+                &config.url.green(),
+            ).ok();
+        }
+
+        if !self.config.locations.is_empty() && config.verbose > 0 {
+            writeln!(
+                io::stdout(),
+                "|{} {}",
+                "Location".magenta(),
+                config.location().to_string().green(),
+            ).ok();
+        }
+
+        if !config.graphql.is_empty() && config.verbose > 0 {
+            writeln!(
+                io::stdout(),
+                "|{} {} of {}",
+                "GraphQL".magenta(),
+                &config.graphql.green(),
+                &config.graphql_field.green(),
+            ).ok();
+        }
+
+        if !config.json_path.is_empty() && config.verbose > 0 {
+            writeln!(
+                io::stdout(),
+                "|{} {}",
+                "Json path".magenta(),
+                &config.json_path.green(),
+            ).ok();
+        }
+
+        if !config.proxy.is_empty() && config.verbose > 0 {
+            writeln!(
+                io::stdout(),
+                "|{} {}",
+                "Proxy".magenta(),
+                &config.proxy.green(),
+            ).ok();
+        }
+
+        //generate random query for the first request
+        let query = make_hashmap(
+            &(0..max).map(|_| random_line(config.value_size*2)).collect::<Vec<String>>(),
+            config.value_size,
+        );
+
+        //get cookies
+        request(config, &mut stats, client, &HashMap::new(), 0).await?;
+
+        // make first request and collect some information like code, reflections, possible parameters
+        let mut initial_response = request(config, &mut stats, client, &query, 0).await?;
+
+        if !config.headers_discovery {
+            for param in heuristic(&initial_response.text) {
+                if !params.contains(&param) {
+                    params.push(param)
+                }
+            }
+        }
+
+        //documented parameters are already sent within every request
+        if let Some(documented_parameters) = &config.documented_parameters {
+            params.retain(|x| !documented_parameters.contains(x));
+        }
+
+        if config.cookies_discovery {
+            params.retain(|x| is_cookie_name(x));
+        }
+
+        //invalid element names would break the xml body
+        if config.as_body && config.body_type.contains("xml") {
+            params.retain(|x| is_xml_name(x));
+        }
+
+        //names from the base query are already known
+        if !config.graphql.is_empty() {
+            let known = query_names(config);
+            params.retain(|x| is_graphql_name(x) && !known.contains(x));
+        }
+
+        if !config.preset.is_empty() {
+            let mut parents: Vec<String> = config.parents.clone();
+            for text in [&config.url, &config.body, &initial_response.text] {
+                for parent in find_parents(text, is_dotted_preset(&config.preset)) {
+                    if !parents.contains(&parent) {
+                        parents.push(parent);
+                    }
+                }
+            }
+
+            if config.verbose > 0 {
+                if parents.is_empty() && config.preset != "php-array" {
+                    writeln!(io::stdout(), "[#] no parents were found for the {} preset, use --parents", config.preset).ok();
+                } else if !parents.is_empty() {
+                    writeln!(io::stdout(), "|{} {}", "Parents".magenta(), parents.join(", ").green()).ok();
+                }
+            }
+
+            params = preset_params(&config.preset, &parents, &params);
+        } else if config.verbose > 0 {
+            //nested keys within the request or the page are worth checking with a preset
+            for (preset, dotted) in [("php-object", false), ("spring", true)] {
+                let mut parents: Vec<String> = Vec::new();
+                for text in [&config.url, &config.body, &initial_response.text] {
+                    parents.append(&mut find_parents(text, dotted));
+                }
+                parents.sort();
+                parents.dedup();
+
+                if !parents.is_empty() {
+                    writeln!(
+                        io::stdout(),
+                        "[#] nested keys were found, try --preset {} --parents {}",
+                        preset,
+                        parents.join(" ")
+                    ).ok();
+                }
+            }
+        }
+
+        if params.len() < max {
+            max = params.len();
+            if max == 0 {
+                params.push(String::from("something"));
+                max = 1;
+            }
+        }
+
+        initial_response.reflected_params = HashMap::new();
+
+        //let reflections count = the number of reflections of the first parameter
+        let reflections_count = count_reflections(
+            &initial_response.text,
+            &initial_response.headers,
+            &query.values().next().unwrap().replace("%random%_", "")
+        );
+
+        if config.verbose > 0 {
+            writeln!(
+                io::stdout(),
+                "|{} {}\n|{} {}\n|{} {}\n|{} {}\n",
+                &"Code".magenta(),
+                &initial_response.code.to_string().green(),
+                &"Response Len".magenta(),
+                &initial_response.text.len().to_string().green(),
+                &"Reflections".magenta(),
+                &reflections_count.to_string().green(),
+                &"Words".magenta(),
+                &params.len().to_string().green(),
+            ).ok();
+        }
+
+        //make a few requests and collect all persistent diffs, check for stability
+        let (mut diffs, mut stable) = empty_reqs(
+            config,
+            &mut stats,
+            &initial_response,
+            reflections_count,
+            config.learn_requests_count,
+            client,
+            max,
+        ).await?;
+
+        //csrf tokens, timestamps and other values that change between identical requests are masked,
+        //so the changes of the body caused by parameters can still be detected
+        let masked_config: Config;
+        let config = if !diffs.is_empty() {
+            let mut texts = vec![initial_response.text.clone()];
+            for _ in 0..MASK_REQUESTS {
+                texts.push(request(config, &mut stats, client, &query, 0).await?.text);
+            }
+
+            let masks = learn_masks(&texts);
+
+            if masks.is_empty() {
+                config
+            } else {
+                if config.verbose > 0 {
+                    writeln!(
+                        io::stdout(),
+                        "[#] {} dynamic parts of the page were masked",
+                        masks.len()
+                    ).ok();
+                }
+
+                initial_response.text = apply_masks(&masks, &initial_response.text);
+                masked_config = Config {
+                    masks: config.masks.iter().chain(masks.iter()).cloned().collect(),
+                    ..config.clone()
+                };
+
+                (diffs, stable) = empty_reqs(
+                    &masked_config,
+                    &mut stats,
+                    &initial_response,
+                    reflections_count,
+                    config.learn_requests_count,
+                    client,
+                    max,
+                ).await?;
+
+                &masked_config
+            }
+        } else {
+            config
+        };
+
+        if config.timing && config.verbose > 0 {
+            writeln!(io::stdout(), "|{} {}", "Response time".magenta(), stable.time.to_string().green()).ok();
+        }
+
+        if config.reflected_only && !stable.reflections {
+            return Err(X8Error::UnstableReflections(config.url.clone()))
+        }
+
+        //check whether it is possible to use 192(128) or 256(196) params in a single request instead of 128 default.
+        //GraphQL servers limit the amount of validation errors, so the max isn't increased for them
+        if (max == 128 || max == 64) && config.graphql.is_empty() {
+            let response = random_request(config, &mut stats, client, reflections_count, max + 64).await?;
+
+            let (is_code_the_same, new_diffs) = compare(&initial_response, &response)?;
+            let mut is_the_body_the_same = true;
+
+            for diff in new_diffs.iter() {
+                if !diffs.iter().any(|i| i == diff) {
+                    is_the_body_the_same = false;
+                }
+            }
+
+            if is_code_the_same && (!stable.body || is_the_body_the_same) {
+                let response = random_request(config, &mut stats, client, reflections_count, max + 128).await?;
+
+                let (is_code_the_same, new_diffs) = compare(&initial_response, &response)?;
+
+                for diff in new_diffs {
+                    if !diffs.iter().any(|i| i == &diff) {
+                        is_the_body_the_same = false;
+                    }
+                }
+
+                if is_code_the_same && (!stable.body || is_the_body_the_same) {
+                    max += 128
+                } else {
+                    max += 64
+                }
+                if config.verbose > 0 {
+                    writeln!(
+                        io::stdout(),
+                        "[#] the max number of parameters in every request was increased to {}",
+                        max
+                    ).ok();
+                }
+            }
+        }
+
+        let initial_max = max;
+        let candidates = params.clone();
+        let typed = config.typed_values && config.as_body && config.body_type.contains("json");
+        let mut typed_values: Vec<ValueType> = if typed {
+            ValueType::TYPED.to_vec()
+        } else {
+            Vec::new()
+        };
+        //parameters with the values from the typed pass, so they aren't confused with custom ones like admin=1
+        let mut typed_params: HashSet<String> = HashSet::new();
+        let mut custom_parameters: HashMap<String, Vec<String>> = config.custom_parameters.clone();
+        let mut remaining_params: Vec<Vec<String>> = Vec::new();
+        let mut found_params: HashMap<String, Finding> = HashMap::new();
+        let mut first: bool = true;
+        let initial_size: usize = params.len() / max;
+        let mut count: usize = 0;
+
+        loop {
+            check_parameters(
+                first,
+                config,
+                &mut stats,
+                &initial_response,
+                &mut diffs,
+                &params,
+                &stable,
+                reflections_count,
+                client,
+                max,
+                &mut green_lines,
+                &mut remaining_params,
+                &mut found_params,
+            ).await?;
+            first = false;
+            count += 1;
+
+            if count > 100
+                || (count > 50 && remaining_params.len() < 10)
+                || (count > 10 && remaining_params.len() > (initial_size / 2 + 5))
+                || (count > 1 && remaining_params.len() > (initial_size * 2 + 10))
+            {
+                return Err(X8Error::InfinityLoop(config.url.clone()))
+            }
+
+            params = Vec::with_capacity(remaining_params.len() * max);
+            max /= 2;
+
+            if max == 0 {
+                max = 1;
+            }
+
+            //if there is a parameter in remaining_params that also exists in found_params - ignore it.
+            let mut found: bool = false;
+            for vector_params in &remaining_params {
+                for param in vector_params {
+                    for found_param in found_params.keys() {
+                        //some strange logic in order to treat admin=1 and admin=something as the same parameters
+                        let param_key = if param.matches('=').count() == 1 {
+                            param.split('=').next().unwrap()
+                        } else {
+                            param
+                        };
+
+                        if found_param == param_key
+                            || found_param.matches('=').count() == 1
+                            && found_param.split('=').next().unwrap() == param_key {
+                            found = true;
+                            break;
+                        }
+                    }
+                    if !found {
+                        params.push(param.to_string());
+                    }
+                    found = false;
+                }
+            }
+
+            if params.is_empty() && !config.disable_custom_parameters {
+                max = config.max;
+                for (k, v) in custom_parameters.iter_mut() {
+                    if !v.is_empty() {
+                        params.push([k.as_str(), "=", v.pop().unwrap().as_str()].concat());
+                    }
+                }
+                if max > params.len() {
+                    max = params.len()
+                }
+            }
+
+            //the whole wordlist is checked again with every type of values
+            if params.is_empty() && !typed_values.is_empty() {
+                let value = typed_values.remove(0).example();
+
+                params = candidates
+                    .iter()
+                    .filter(|x| !x.contains('=') && !found_params.keys().any(|found| found.split('=').next() == Some(x.as_str())))
+                    .map(|x| [x.as_str(), "=", value].concat())
+                    .collect();
+                typed_params.extend(params.iter().cloned());
+
+                max = initial_max.min(params.len());
+                count = 0;
+            }
+
+            if params.is_empty() {
+                break;
+            }
+
+            remaining_params = Vec::new()
+        }
+
+        if config.verify {
+            let mut filtered_params = HashMap::with_capacity(found_params.len());
+            for (param, finding) in found_params {
+
+                let response = request(
+                    config,
+                    &mut stats,
+                    client,
+                    &make_hashmap(
+                        std::slice::from_ref(&param), config.value_size
+                    ),
+                    reflections_count
+                ).await?;
+
+                let (is_code_the_same, new_diffs) = compare(&initial_response, &response)?;
+                let mut is_the_body_the_same = true;
+
+                for diff in new_diffs.iter() {
+                    if !diffs.iter().any(|i| i==diff) {
+                        is_the_body_the_same = false;
+                    }
+                }
+
+                let is_slow = config.timing && stable.time.is_slow(response.time);
+
+                if !response.reflected_params.is_empty() || !is_the_body_the_same || !is_code_the_same || is_slow {
+                    filtered_params.insert(param, finding);
+                }
+            }
+            found_params = filtered_params;
+        }
+
+        if config.hpp {
+            for (param, finding) in found_params.iter_mut() {
+                let name = param.split('=').next().unwrap_or(param);
+
+                finding.pollution = check_pollution(
+                    config, &mut stats, client, param, &initial_response, &diffs, reflections_count
+                ).await?;
+
+                if let (Some(pollution), true) = (&finding.pollution, config.verbose > 0) {
+                    writeln!(io::stdout(), "{}: {}", name, pollution).ok();
+                }
+            }
+        }
+
+        //the chars can't be put into GraphQL queries as they are
+        if config.special_chars && config.graphql.is_empty() {
+            for (param, finding) in found_params.iter_mut().filter(|(_, x)| x.reason == Reason::Reflects) {
+                let name = param.split('=').next().unwrap_or(param);
+
+                finding.special_chars = probe_special_chars(config, &mut stats, client, name, reflections_count).await?;
+
+                if config.verbose > 0 {
+                    writeln!(
+                        io::stdout(),
+                        "{}: {}",
+                        name,
+                        finding.special_chars.iter().map(|x| x.to_string()).collect::<Vec<String>>().join(", ")
+                    ).ok();
+                }
+            }
+        }
+
+        //fields of GraphQL types don't have values
+        if !config.enumerate_values.is_empty() && config.graphql != "fields" {
+            for (param, finding) in found_params.iter_mut() {
+                let name = param.split('=').next().unwrap_or(param);
+
+                finding.values = enumerate_values(config, &mut stats, client, name, reflections_count).await?;
+
+                if !finding.values.is_empty() && config.verbose > 0 {
+                    writeln!(
+                        io::stdout(),
+                        "{}: {}",
+                        name,
+                        finding.values.iter().map(|x| x.to_string()).collect::<Vec<String>>().join(", ")
+                    ).ok();
+                }
+            }
+        }
+
+        if let Some(replay_client) = &self.replay_client {
+            let temp_config = Config{
+                disable_cachebuster: true,
+                ..config.clone()
+            };
+
+            request(&temp_config, &mut stats, replay_client, &HashMap::new(), 0).await.ok();
+
+            if config.replay_once {
+                request(
+                    &temp_config,
+                    &mut stats,
+                    replay_client,
+                    &make_hashmap(
+                        &found_params.keys().map(|x| x.to_owned()).collect::<Vec<String>>(),
+                        config.value_size
+                    ),
+                    0
+                ).await.ok();
+            } else {
+                for param in found_params.keys() {
+                    request(
+                        &temp_config,
+                        &mut stats,
+                        replay_client,
+                        &make_hashmap(
+                            &[param.to_owned()],
+                            config.value_size
+                        ),
+                        0
+                    ).await.ok();
+                }
+            }
+        }
+
+        if config.verbose > 0 {
+            writeln!(io::stdout(),"\n{}: {}", &"Amount of requests".magenta(), stats.amount_of_requests).ok();
+        }
+
+        if typed {
+            for (param, finding) in found_params.iter_mut() {
+                //the wordlist is checked with string values first
+                finding.value_type = match param.split_once('=') {
+                    Some((_, value)) if typed_params.contains(param) => Some(ValueType::from_value(value)),
+                    Some(_) => None,
+                    None => Some(ValueType::String),
+                };
+            }
+        }
+
+        //parameters from nested json objects are reported with the full path
+        if !config.json_path.is_empty() {
+            for finding in found_params.values_mut() {
+                finding.name = format!("{}.{}", config.json_path, finding.name);
+            }
+        }
+
+        for finding in found_params.values_mut() {
+            finding.locations = vec![config.location()];
+        }
+
+        //user(debug) for arguments and user.debug for selection fields
+        if !config.graphql.is_empty() {
+            for finding in found_params.values_mut() {
+                finding.name = if config.graphql == "arguments" {
+                    format!("{}({})", config.graphql_field, finding.name)
+                } else {
+                    format!("{}.{}", config.graphql_field, finding.name)
+                };
+            }
+        }
+
+        Ok(ScanResult {
+            found_params,
+            amount_of_requests: stats.amount_of_requests,
+            initial_code: initial_response.code,
+            initial_size: initial_response.text.len(),
+            initial_response: initial_response.full_text(),
+            reflections_count,
+            max: initial_max,
+        })
+    }
+}
@@ -1,4 +1,7 @@
-use std::{collections::HashMap, time::Duration};
+use regex::Regex;
+use serde::Serialize;
+use std::{collections::HashMap, fmt, sync::Arc, time::Duration};
+use tokio::sync::Semaphore;
 
 pub trait DefaultResponse {
     fn default() -> ResponseData;
@@ -6,9 +9,44 @@ pub trait DefaultResponse {
 
 #[derive(Debug)]
 pub struct ResponseData {
+    //the body. Headers are kept separately and compared by their own rules
     pub text: String,
     pub code: u16,
+    //lowercase names sorted alphabetically. Repeated headers have an entry for every value
+    pub headers: Vec<(String, String)>,
     pub reflected_params: HashMap<String, usize>,
+    //validation errors about the sent candidates. They are removed from the text
+    pub graphql_errors: GraphqlErrors,
+    //from sending the request till the body is read
+    pub time: Duration,
+}
+
+//names from the GraphQL validation errors
+#[derive(Debug, Default)]
+pub struct GraphqlErrors {
+    //candidates that were reported as unknown arguments or fields
+    pub unknown: Vec<String>,
+    //names from the "Did you mean" suggestions
+    pub suggestions: Vec<String>,
+    //the server stopped the validation after too many errors
+    pub truncated: bool,
+}
+
+impl ResponseData {
+    //headers and body the way they are shown within reports and saved responses
+    pub fn full_text(&self) -> String {
+        let mut text = String::new();
+        for (key, value) in self.headers.iter() {
+            text.push_str(key);
+            text.push_str(": ");
+            text.push_str(value);
+            text.push('\n');
+        }
+        text.push_str("\n\n");
+        text.push_str(&self.text);
+
+        text
+    }
 }
 
 impl DefaultResponse for ResponseData {
@@ -16,15 +54,392 @@ impl DefaultResponse for ResponseData {
         ResponseData {
             text: String::new(),
             code: 0u16,
+            headers: Vec::new(),
             reflected_params: HashMap::new(),
+            graphql_errors: GraphqlErrors::default(),
+            time: Duration::ZERO,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Reason {
+    //the parameter has a different amount of reflections
+    Reflects,
+    CausesOtherReflections,
+    ChangesPage,
+    ChangesCode,
+    //a header is added, removed or changed, or a new cookie is set
+    ChangesHeaders,
+    //the response is significantly slower than usual
+    ChangesTime,
+    //the GraphQL server didn't report the name as unknown
+    GraphqlAccepted,
+    GraphqlSuggested,
+}
+
+impl Reason {
+    //the same name that is used in json output
+    pub fn id(&self) -> String {
+        match serde_json::to_value(self) {
+            Ok(serde_json::Value::String(val)) => val,
+            _ => String::new(),
+        }
+    }
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            Reason::Reflects => "Different amount of reflections",
+            Reason::CausesOtherReflections => "Causes other parameters to reflect different times",
+            Reason::ChangesPage => "Changes page",
+            Reason::ChangesCode => "Changes response code",
+            Reason::ChangesHeaders => "Changes response headers",
+            Reason::ChangesTime => "Changes response time",
+            Reason::GraphqlAccepted => "Accepted by the GraphQL schema",
+            Reason::GraphqlSuggested => "Suggested by the GraphQL server",
+        }
+    }
+}
+
+//json type of the value that was sent with a parameter
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ValueType {
+    String,
+    Number,
+    Boolean,
+    Array,
+    Object,
+}
+
+impl ValueType {
+    //types that are checked within the typed pass. Strings are checked by the usual one
+    pub const TYPED: [ValueType; 4] = [ValueType::Number, ValueType::Boolean, ValueType::Array, ValueType::Object];
+
+    //the value that is sent without quotes in json bodies
+    pub fn example(&self) -> &'static str {
+        match self {
+            ValueType::String => "",
+            ValueType::Number => "1",
+            ValueType::Boolean => "true",
+            ValueType::Array => "[1]",
+            ValueType::Object => "{}",
+        }
+    }
+
+    pub fn from_value(value: &str) -> ValueType {
+        if value.parse::<f64>().is_ok() {
+            ValueType::Number
+        } else if value == "true" || value == "false" {
+            ValueType::Boolean
+        } else if value.starts_with('[') && value.ends_with(']') {
+            ValueType::Array
+        } else if value.starts_with('{') && value.ends_with('}') {
+            ValueType::Object
+        } else {
+            ValueType::String
+        }
+    }
+}
+
+impl fmt::Display for ValueType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match serde_json::to_value(self) {
+            Ok(serde_json::Value::String(val)) => write!(f, "{}", val),
+            _ => Ok(()),
+        }
+    }
+}
+
+//where parameters are sent
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Location {
+    Query,
+    Body,
+    //header names
+    Headers,
+    //the %s injection point within a header value
+    HeaderValue,
+    Cookies,
+    Path,
+}
+
+impl Location {
+    //locations that are checked in the mixed mode
+    pub const MIXED: [Location; 4] = [Location::Query, Location::Body, Location::Cookies, Location::Headers];
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match serde_json::to_value(self) {
+            Ok(serde_json::Value::String(val)) => write!(f, "{}", val),
+            _ => Ok(()),
         }
     }
 }
 
+//which of the duplicated values the backend uses
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Precedence {
+    First,
+    Last,
+    //a,b
+    Concatenated,
+    //both values are used separately, like an array
+    Both,
+    //neither of the values is used, e.g. p[] isn't read as p
+    Ignored,
+    //the values aren't reflected and lead to the same page, so it isn't visible which one is used
+    Unknown,
+}
+
+impl fmt::Display for Precedence {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match serde_json::to_value(self) {
+            Ok(serde_json::Value::String(val)) => write!(f, "{}", val),
+            _ => Ok(()),
+        }
+    }
+}
+
+//the results of the http parameter pollution checks
+#[derive(Debug, Clone, Serialize)]
+pub struct Pollution {
+    //p=a&p=b
+    pub duplicates: Precedence,
+    //p[]=a&p[]=b
+    pub array: Precedence,
+    //p=a within the query and p=b within the body. First means that the query copy is used
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub query_and_body: Option<Precedence>,
+}
+
+impl fmt::Display for Pollution {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "duplicates: {}, array: {}", self.duplicates, self.array)?;
+
+        match self.query_and_body {
+            Some(Precedence::First) => write!(f, ", query and body: query"),
+            Some(Precedence::Last) => write!(f, ", query and body: body"),
+            Some(precedence) => write!(f, ", query and body: {}", precedence),
+            None => Ok(()),
+        }
+    }
+}
+
+//where the value of a parameter is reflected
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReflectionContext {
+    HtmlText,
+    QuotedAttribute,
+    UnquotedAttribute,
+    Script,
+    JsonString,
+    Comment,
+    Header,
+}
+
+impl fmt::Display for ReflectionContext {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match serde_json::to_value(self) {
+            Ok(serde_json::Value::String(val)) => write!(f, "{}", val),
+            _ => Ok(()),
+        }
+    }
+}
+
+//a single reflection with the text around it
+#[derive(Debug, Clone, Serialize)]
+pub struct Reflection {
+    pub context: ReflectionContext,
+    pub snippet: String,
+}
+
+//how a special char is returned within the reflection
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CharStatus {
+    //the order is from the most to the least useful one
+    Raw,
+    //\" or \u0022
+    Escaped,
+    HtmlEncoded,
+    UrlEncoded,
+    Replaced,
+    Stripped,
+    NotReflected,
+}
+
+impl fmt::Display for CharStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match serde_json::to_value(self) {
+            Ok(serde_json::Value::String(val)) => write!(f, "{}", val),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SpecialChar {
+    #[serde(rename = "char")]
+    pub special_char: char,
+    pub status: CharStatus,
+}
+
+impl fmt::Display for SpecialChar {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {}", self.special_char, self.status)
+    }
+}
+
+//a value of a found parameter that changes the response
+#[derive(Debug, Clone, Serialize)]
+pub struct ValueResponse {
+    pub value: String,
+    pub code: u16,
+    pub length: usize,
+}
+
+impl fmt::Display for ValueResponse {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} ({}, {})", self.value, self.code, self.length)
+    }
+}
+
+//a found parameter with the evidence it was found by
+#[derive(Debug, Clone, Serialize)]
+pub struct Finding {
+    pub name: String,
+    pub reason: Reason,
+    pub code_before: u16,
+    pub code_after: u16,
+    pub length_before: usize,
+    pub length_after: usize,
+    pub reflections_before: usize,
+    pub reflections_after: usize,
+    //in milliseconds. The time before is the mean time of the learning requests for parameters found by the response time
+    pub time_before: u64,
+    pub time_after: u64,
+    //the header change like "Sets cookie debug". Only for parameters that change headers
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub header_change: Option<String>,
+    //the request that triggered the difference
+    pub request: String,
+    //the response to that request. Used to show the diff with the initial response
+    #[serde(skip)]
+    pub response: String,
+    //the type of the value that triggered the difference. Set within the typed json pass
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value_type: Option<ValueType>,
+    //every location the parameter was accepted in
+    pub locations: Vec<Location>,
+    //how duplicates of the parameter are treated. Checked with --hpp
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pollution: Option<Pollution>,
+    //where the value is reflected. Only for parameters with a different amount of reflections
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub reflections: Vec<Reflection>,
+    //how special chars are reflected. Checked with --special-chars
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub special_chars: Vec<SpecialChar>,
+    //values that produce distinct responses. Checked with --enumerate
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub values: Vec<ValueResponse>,
+}
+
+impl Finding {
+    pub fn new(
+        name: &str,
+        reason: Reason,
+        initial_response: &ResponseData,
+        response: &ResponseData,
+        reflections_count: usize,
+        request: String,
+    ) -> Finding {
+        Finding {
+            name: name.to_string(),
+            reason,
+            code_before: initial_response.code,
+            code_after: response.code,
+            length_before: initial_response.text.len(),
+            length_after: response.text.len(),
+            reflections_before: reflections_count,
+            reflections_after: *response.reflected_params.get(name).unwrap_or(&reflections_count),
+            time_before: initial_response.time.as_millis() as u64,
+            time_after: response.time.as_millis() as u64,
+            header_change: None,
+            request,
+            response: response.full_text(),
+            value_type: None,
+            locations: Vec::new(),
+            pollution: None,
+            reflections: Vec::new(),
+            special_chars: Vec::new(),
+            values: Vec::new(),
+        }
+    }
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.reason {
+            Reason::Reflects | Reason::CausesOtherReflections
+                | Reason::GraphqlAccepted | Reason::GraphqlSuggested => write!(f, "{}", self.reason.title())?,
+            Reason::ChangesPage => write!(f, "Changes page: {} -> {}", self.length_before, self.length_after)?,
+            Reason::ChangesCode => write!(f, "Changes response code: {} -> {}", self.code_before, self.code_after)?,
+            Reason::ChangesHeaders => match &self.header_change {
+                Some(change) => write!(f, "{}", change)?,
+                None => write!(f, "{}", self.reason.title())?,
+            },
+            Reason::ChangesTime => write!(f, "Changes response time: {}ms -> {}ms", self.time_before, self.time_after)?,
+        }
+
+        if !self.reflections.is_empty() {
+            let mut contexts: Vec<String> = Vec::new();
+            for reflection in self.reflections.iter() {
+                if !contexts.contains(&reflection.context.to_string()) {
+                    contexts.push(reflection.context.to_string());
+                }
+            }
+            write!(f, " in {}", contexts.join(", "))?;
+        }
+
+        if !self.special_chars.is_empty() {
+            write!(
+                f,
+                " chars: {}",
+                self.special_chars.iter().map(|x| x.to_string()).collect::<Vec<String>>().join(", ")
+            )?;
+        }
+
+        if let Some(value_type) = self.value_type {
+            write!(f, " ({} value)", value_type)?;
+        }
+
+        if let Some(pollution) = &self.pollution {
+            write!(f, " [{}]", pollution)?;
+        }
+
+        if !self.values.is_empty() {
+            write!(
+                f,
+                " values: {}",
+                self.values.iter().map(|x| x.to_string()).collect::<Vec<String>>().join(", ")
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct FuturesData {
     pub remaining_params: Vec<String>,
-    pub found_params: HashMap<String, String>,
+    pub found_params: HashMap<String, Finding>,
     pub stats: Statistic
 }
 
@@ -58,6 +473,8 @@ pub struct Config {
     pub as_body: bool,
     pub headers_discovery: bool,
     pub within_headers: bool,
+    //parameters are sent as cookies within the Cookie header
+    pub cookies_discovery: bool,
     pub verbose: usize,
     pub is_json: bool,
     pub disable_cachebuster: bool,
@@ -66,14 +483,200 @@ pub struct Config {
     pub learn_requests_count: usize,
     pub max: usize,
     pub concurrency: usize,
+    pub workers: usize,
+    //limits the amount of requests in flight across every target that shares it. None means no limit
+    pub requests_limit: Option<Arc<Semaphore>>,
     pub verify: bool,
-    pub reflected_only: bool
+    pub reflected_only: bool,
+    //found parameters that aren't reported as failures in junit reports
+    pub expected_parameters: Vec<String>,
+    //parameters from the OpenAPI spec. They are sent within every request and aren't checked
+    pub documented_parameters: Option<Vec<String>>,
+    //path to the json object with the injection point, i.e user.settings
+    pub json_path: String,
+    //check parameters with number, boolean, array and object values in json bodies
+    pub typed_values: bool,
+    //"arguments" or "fields" of graphql_field are searched for. Empty when the GraphQL mode is off
+    pub graphql: String,
+    pub graphql_field: String,
+    //one of utils::PRESETS. Words are checked as nested keys of the parents
+    pub preset: String,
+    pub parents: Vec<String>,
+    //"matrix" - ;k=v or "segments" - /k/v at the end of the path. Empty when parameters aren't sent within the path
+    pub path_injection: String,
+    //locations that are scanned one by one for the same target. Empty unless the mixed mode is used
+    pub locations: Vec<Location>,
+    //check how duplicates of the found parameters are treated
+    pub hpp: bool,
+    //values the found parameters are checked with. Empty unless --enumerate is used
+    pub enumerate_values: Vec<String>,
+    //dynamic parts of the page like csrf tokens. They are replaced before responses are compared
+    pub masks: Vec<Regex>,
+    //significantly slower responses are treated as changes
+    pub timing: bool,
+    //check how special chars are reflected by the reflected parameters
+    pub special_chars: bool,
+}
+
+impl Config {
+    //where the parameters are sent. Derived from the separate flags, so they are interpreted the same way everywhere
+    pub fn location(&self) -> Location {
+        if self.as_body {
+            Location::Body
+        } else if self.cookies_discovery {
+            Location::Cookies
+        } else if self.within_headers {
+            Location::HeaderValue
+        } else if self.headers_discovery {
+            Location::Headers
+        } else if !self.path_injection.is_empty() {
+            Location::Path
+        } else {
+            Location::Query
+        }
+    }
+
+    //cookies from responses could replace the injected ones
+    pub fn cookie_store(&self) -> bool {
+        !self.cookies_discovery && !self.locations.contains(&Location::Cookies)
+    }
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config {
+            method: String::from("GET"),
+            initial_url: String::new(),
+            url: String::new(),
+            host: String::new(),
+            path: String::new(),
+            wordlist: String::new(),
+            parameter_template: String::from("%k=%v&"),
+            custom_parameters: HashMap::new(),
+            headers: HashMap::new(),
+            body: String::new(),
+            body_type: String::from("urlencode-"),
+            proxy: String::new(),
+            output_file: String::new(),
+            output_format: String::from("standart"),
+            save_responses: String::new(),
+            force: false,
+            disable_response_correction: false,
+            disable_custom_parameters: false,
+            disable_progress_bar: true,
+            replay_once: false,
+            replay_proxy: String::new(),
+            follow_redirects: false,
+            encode: false,
+            test: false,
+            append: false,
+            as_body: false,
+            headers_discovery: false,
+            within_headers: false,
+            cookies_discovery: false,
+            verbose: 0,
+            is_json: false,
+            disable_cachebuster: false,
+            delay: Duration::from_millis(0),
+            value_size: 7,
+            learn_requests_count: 9,
+            max: 128,
+            concurrency: 1,
+            workers: 1,
+            requests_limit: None,
+            verify: false,
+            reflected_only: false,
+            expected_parameters: Vec::new(),
+            documented_parameters: None,
+            json_path: String::new(),
+            typed_values: false,
+            graphql: String::new(),
+            graphql_field: String::new(),
+            preset: String::new(),
+            parents: Vec::new(),
+            path_injection: String::new(),
+            locations: Vec::new(),
+            hpp: false,
+            enumerate_values: Vec::new(),
+            masks: Vec::new(),
+            timing: false,
+            special_chars: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ScanResult {
+    pub found_params: HashMap<String, Finding>,
+    pub amount_of_requests: usize,
+    pub initial_code: u16,
+    pub initial_size: usize,
+    pub initial_response: String,
+    pub reflections_count: usize,
+    //the max number of parameters in every request after the tuning
+    pub max: usize,
+}
+
+impl ScanResult {
+    //adds the findings from another location of the same target
+    pub fn merge(&mut self, result: ScanResult) {
+        self.amount_of_requests += result.amount_of_requests;
+
+        for (param, finding) in result.found_params {
+            match self.found_params.get_mut(&param) {
+                Some(found) => found.locations.extend(finding.locations),
+                None => {
+                    self.found_params.insert(param, finding);
+                }
+            }
+        }
+    }
 }
 
 #[derive(Debug)]
 pub struct Stable {
     pub body: bool,
     pub reflections: bool,
+    pub time: Timing,
+}
+
+//the distribution of response times of the learning requests in milliseconds
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Timing {
+    pub mean: f64,
+    pub deviation: f64,
+}
+
+impl Timing {
+    //the amount of standard deviations a response has to be slower by
+    const DEVIATIONS: f64 = 5.0;
+    //small differences are caused by the network rather than by the parameters
+    const MIN_DIFFERENCE: f64 = 250.0;
+
+    pub fn new(times: &[Duration]) -> Timing {
+        if times.is_empty() {
+            return Timing::default()
+        }
+
+        let times: Vec<f64> = times.iter().map(|x| x.as_secs_f64() * 1000.0).collect();
+        let mean = times.iter().sum::<f64>() / times.len() as f64;
+        let variance = times.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / times.len() as f64;
+
+        Timing {
+            mean,
+            deviation: variance.sqrt(),
+        }
+    }
+
+    pub fn is_slow(&self, time: Duration) -> bool {
+        time.as_secs_f64() * 1000.0 > self.mean + (self.deviation * Timing::DEVIATIONS).max(Timing::MIN_DIFFERENCE)
+    }
+}
+
+impl fmt::Display for Timing {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:.0}ms ± {:.0}ms", self.mean, self.deviation)
+    }
 }
 
 #[derive(Debug, Clone)]
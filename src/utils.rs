@@ -1,5 +1,6 @@
+use crate::error::X8Error;
 use crate::requests::request;
-use crate::structs::{Config, ResponseData, Statistic};
+use crate::structs::{Config, Finding, Location, ResponseData, ScanResult, Statistic};
 use crate::diff::diff;
 
 use lazy_static::lazy_static;
@@ -7,6 +8,8 @@ use percent_encoding::{utf8_percent_encode, AsciiSet, CONTROLS};
 use rand::Rng;
 use regex::Regex;
 use reqwest::Client;
+use serde::Serialize;
+use url::Url;
 use std::{
     collections::HashMap,
     fs::File,
@@ -27,8 +30,25 @@ lazy_static! {
         .add(b'/')
         .add(b'=')
         .add(b'%');
+    //every key and value is a separate path segment
+    static ref PATH_SEGMENT: AsciiSet = CONTROLS
+        .add(b' ')
+        .add(b'"')
+        .add(b'<')
+        .add(b'>')
+        .add(b'`')
+        .add(b'#')
+        .add(b'?')
+        .add(b'{')
+        .add(b'}')
+        .add(b'/')
+        .add(b'\\')
+        .add(b';')
+        .add(b',')
+        .add(b'=')
+        .add(b'%');
     static ref RE_JSON_WORDS_WITHOUT_QUOTES: Regex =
-        Regex::new(r#"^(\d+|null|false|true)$"#).unwrap();
+        Regex::new(r#"^(\d+|null|false|true|\[.*\]|\{.*\})$"#).unwrap();
     static ref RE_JSON_BRACKETS: Regex =
         Regex::new(r#"(?P<bracket>(\{"|"\}|\[("|\d)|("|\d)\]))"#).unwrap();
     static ref RE_JSON_COMMA_AFTER_DIGIT: Regex =
@@ -43,7 +63,7 @@ lazy_static! {
 pub fn compare(
     initial_response: &ResponseData,
     response: &ResponseData,
-) -> (bool, Vec<String>) {
+) -> Result<(bool, Vec<String>), X8Error> {
 
     let mut code: bool = true;
     let mut diffs: Vec<String> = Vec::new();
@@ -53,16 +73,10 @@ pub fn compare(
     }
 
     //just push every found diff to the vector of diffs
-    for diff in match diff(
+    for diff in diff(
         &initial_response.text,
         &response.text,
-    ) {
-        Ok(val) => val,
-        Err(err) => {
-            writeln!(io::stderr(), "Unable to compare: {}", err).ok(); //TODO return error instead
-            std::process::exit(1);
-        }
-    } {
+    ).map_err(X8Error::Diff)? {
         if !diffs.contains(&diff) {
             diffs.push(diff);
         } else {
@@ -74,7 +88,85 @@ pub fn compare(
         }
     }
 
-    (code, diffs)
+    diffs.append(&mut compare_headers(&initial_response.headers, &response.headers));
+
+    Ok((code, diffs))
+}
+
+//headers that differ between identical requests. The content length is compared within the body
+const NOISY_HEADERS: [&str; 18] = [
+    "age", "cf-ray", "content-length", "date", "etag", "expires", "last-modified", "nel", "report-to",
+    "request-id", "server-timing", "traceparent", "x-amz-cf-id", "x-amzn-requestid", "x-amzn-trace-id",
+    "x-correlation-id", "x-request-id", "x-runtime",
+];
+
+//changes of the headers like "Adds header X-Debug", "Changes Location" or "Sets cookie debug".
+//Values of cookies and headers that differ between identical requests are ignored
+pub fn compare_headers(initial_headers: &[(String, String)], headers: &[(String, String)]) -> Vec<String> {
+    let values = |headers: &[(String, String)], name: &str| -> Vec<String> {
+        headers.iter().filter(|(k, _)| k == name).map(|(_, v)| v.to_string()).collect()
+    };
+
+    let mut names: Vec<&str> = initial_headers.iter().chain(headers.iter()).map(|(k, _)| k.as_str()).collect();
+    names.sort();
+    names.dedup();
+
+    let mut changes: Vec<String> = Vec::new();
+
+    for name in names {
+        if NOISY_HEADERS.contains(&name) {
+            continue
+        }
+
+        if name == "set-cookie" {
+            let cookie_names = |headers: &[(String, String)]| -> Vec<String> {
+                values(headers, name)
+                    .iter()
+                    .map(|x| x.split(['=', ';']).next().unwrap_or("").trim().to_string())
+                    .collect()
+            };
+            let (before, after) = (cookie_names(initial_headers), cookie_names(headers));
+
+            for cookie in after.iter().filter(|x| !before.contains(x)) {
+                changes.push(format!("Sets cookie {}", cookie));
+            }
+            for cookie in before.iter().filter(|x| !after.contains(x)) {
+                changes.push(format!("Doesn't set cookie {}", cookie));
+            }
+            continue
+        }
+
+        let (before, after) = (values(initial_headers, name), values(headers, name));
+
+        if before.is_empty() {
+            changes.push(format!("Adds header {}", title_case(name)));
+        } else if after.is_empty() {
+            changes.push(format!("Removes header {}", title_case(name)));
+        } else if before != after {
+            changes.push(format!("Changes {}", title_case(name)));
+        }
+    }
+
+    changes
+}
+
+//body diffs are line numbers like "-1,2 +1,3", header changes are sentences
+pub fn is_header_change(diff: &str) -> bool {
+    !diff.starts_with('-')
+}
+
+//x-forwarded-for -> X-Forwarded-For
+fn title_case(name: &str) -> String {
+    name.split('-')
+        .map(|x| {
+            let mut chars = x.chars();
+            match chars.next() {
+                Some(c) => c.to_uppercase().chain(chars).collect(),
+                None => String::new(),
+            }
+        })
+        .collect::<Vec<String>>()
+        .join("-")
 }
 
 //get possible parameters from the page code
@@ -111,7 +203,7 @@ pub fn heuristic(body: &str) -> Vec<String> {
 }
 
 //remove forbidden characters from header name, otherwise reqwest throws errors
-pub fn fix_headers<'a>(header: &'a str) -> Option<String> {
+pub fn fix_headers(header: &str) -> Option<String> {
     lazy_static! {
         static ref RE: Regex = Regex::new(r"[^!-'*+\-\.0-9a-zA-Z^-`|~]").unwrap();
     }
@@ -120,32 +212,49 @@ pub fn fix_headers<'a>(header: &'a str) -> Option<String> {
         Some(RE.replace_all(header, "").to_string())
 
     // hyper throws an error in case the Content-Length header contains random value with http2
-    } else if header.to_ascii_lowercase() == "content-length" {
+    } else if header.eq_ignore_ascii_case("content-length") {
         Some(String::from("disabled"))
     } else {
         None
     }
 }
 
-pub fn generate_request(config: &Config, initial_query: &HashMap<String, String>) -> String {
-    let mut hashmap_query: HashMap<String, String> = HashMap::with_capacity(initial_query.len());
-    for (k, v) in initial_query.iter() {
-        hashmap_query.insert(k.to_string(), v.replace("%random%_", ""));
-    }
-
-    let query: String = if !hashmap_query.is_empty() {
-        if config.as_body {
-            make_body(&config, &hashmap_query)
-        } else if config.within_headers {
-            make_header_value(&config, &hashmap_query)
-        } else if config.headers_discovery {
-            String::new()
-        } else {
-            make_query(&config, &hashmap_query)
+//merges cookie headers into a single one with the injection point at the end
+pub fn add_cookie_injection(headers: &mut HashMap<String, String>) {
+    let keys: Vec<String> = headers.keys().filter(|x| x.eq_ignore_ascii_case("cookie")).cloned().collect();
+    let mut cookies: Vec<String> = Vec::new();
+
+    for key in keys {
+        let value = headers.remove(&key).unwrap_or_default();
+        for cookie in value.split(';').map(|x| x.trim()).filter(|x| !x.is_empty() && *x != "%s") {
+            if !cookies.iter().any(|x| x == cookie) {
+                cookies.push(cookie.to_string());
+            }
         }
-    } else {
-        String::new()
-    };
+    }
+
+    cookies.push(String::from("%s"));
+    headers.insert(String::from("Cookie"), cookies.join("; "));
+}
+
+//cookie names are tokens, so they can't contain separators like ; = , or spaces
+pub fn is_cookie_name(name: &str) -> bool {
+    lazy_static! {
+        static ref RE: Regex = Regex::new(r"^[!#-'*+\-\.0-9A-Z^-z|~]+$").unwrap();
+    }
+
+    //parameters with custom values look like name=value
+    RE.is_match(name.split('=').next().unwrap_or(""))
+}
+
+pub fn generate_request(config: &Config, initial_query: &HashMap<String, String>) -> String {
+    let pairs: Vec<(String, String)> = initial_query
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.replace("%random%_", "")))
+        .collect();
+
+    let query: String = make_injection(config, &pairs);
+    let location = config.location();
 
     let mut req: String = String::with_capacity(4096);
     req.push_str(&config.url);
@@ -165,7 +274,7 @@ pub fn generate_request(config: &Config, initial_query: &HashMap<String, String>
     for (key, value) in config.headers.iter() {
         req.push_str(key);
         req.push_str(": ");
-        if value.contains("%s") && config.headers_discovery && config.within_headers {
+        if value.contains("%s") && matches!(location, Location::HeaderValue | Location::Cookies) {
             req.push_str(&value.replace("%s", &query).replace("{{random}}", &random_line(config.value_size)));
         } else {
             req.push_str(&value.replace("{{random}}", &random_line(config.value_size)));
@@ -173,8 +282,8 @@ pub fn generate_request(config: &Config, initial_query: &HashMap<String, String>
         req.push('\n');
     }
 
-    if config.headers_discovery && !config.within_headers {
-        for (key, value) in hashmap_query.iter() {
+    if location == Location::Headers {
+        for (key, value) in pairs.iter() {
             req.push_str(key);
             req.push_str(": ");
             req.push_str(&value.replace("{{random}}", &random_line(config.value_size)));
@@ -182,7 +291,7 @@ pub fn generate_request(config: &Config, initial_query: &HashMap<String, String>
         }
     }
 
-    if config.as_body && !query.is_empty() {
+    if location == Location::Body && !query.is_empty() {
         req.push('\n');
         req.push_str(&query);
         req.push('\n');
@@ -191,21 +300,30 @@ pub fn generate_request(config: &Config, initial_query: &HashMap<String, String>
     req
 }
 
+//the same as generate_request but without the target url in the first line
+pub fn generate_http_request(config: &Config, query: &HashMap<String, String>) -> String {
+    generate_request(config, query)
+        .lines()
+        .skip(1)
+        .collect::<Vec<&str>>()
+        .join("\n") + "\n"
+}
+
 //prints request and response
-pub async fn generate_data(config: &Config, stats: &mut Statistic, client: &Client, query: &HashMap<String, String>) -> Option<()> {
+pub async fn generate_data(config: &Config, stats: &mut Statistic, client: &Client, query: &HashMap<String, String>) -> Result<(), X8Error> {
     let req = generate_request(config, query);
 
     writeln!(io::stdout(), "Request:\n{}", req).ok();
 
     let response =
-        request(config, stats, client, &query, 0)
+        request(config, stats, client, query, 0)
             .await?;
 
     writeln!(
         io::stdout(),
         "Response:\nCode: {}\n\n{}",
         response.code,
-        response.text
+        response.full_text()
     ).ok();
 
     writeln!(
@@ -214,13 +332,18 @@ pub async fn generate_data(config: &Config, stats: &mut Statistic, client: &Clie
         heuristic(&response.text).join(", ")
     ).ok();
 
-    Some(())
+    Ok(())
 }
 
 //Add %s if it is absent in the body
 pub fn adjust_body(body: &str, t: &str) -> String {
     let mut body = body.to_string();
 
+    //if type is xml -> add an injection to the element with the last leaf element
+    if t.contains("xml") {
+        return adjust_xml_body(&body)
+    }
+
     //if type is json and body has parameters -> add an injection to the end
     if t.contains("json") && !body.is_empty() && body.contains('"') {
         body.pop();
@@ -238,15 +361,327 @@ pub fn adjust_body(body: &str, t: &str) -> String {
     }
 }
 
-pub fn make_header_value(config: &Config, query: &HashMap<String, String>) -> String {
+//the key that marks the object for the injection point while the body is parsed
+const JSON_MARKER: &str = "x8-injection-point";
+
+//returns a config for every object within the json body when `nested` is true.
+//Otherwise the config is returned as it is.
+//The path to the object is kept to report parameters like user.settings.debug
+pub fn json_body_configs(config: Config, nested: bool) -> Vec<Config> {
+    if !nested || !config.as_body || !config.body_type.contains("json") {
+        return vec![config]
+    }
+
+    let marked_body = config.body.replacen("%s", &format!("\"{}\":null", JSON_MARKER), 1);
+
+    //bodies with variables outside of strings can't be parsed
+    let mut value: serde_json::Value = match serde_json::from_str(&marked_body) {
+        Ok(val) => val,
+        Err(_) => return vec![config]
+    };
+
+    let mut objects: Vec<(String, String)> = Vec::new();
+    find_json_objects(&value, "", "", &mut objects);
+
+    for (pointer, _) in objects.iter() {
+        if let Some(object) = value.pointer_mut(pointer).and_then(|x| x.as_object_mut()) {
+            object.remove(JSON_MARKER);
+        }
+    }
+
+    let mut configs: Vec<Config> = Vec::with_capacity(objects.len());
+
+    for (pointer, path) in objects {
+        let mut value = value.clone();
+        if let Some(object) = value.pointer_mut(&pointer).and_then(|x| x.as_object_mut()) {
+            object.insert(JSON_MARKER.to_string(), serde_json::Value::Null);
+        }
+
+        configs.push(Config {
+            body: value.to_string().replace(&format!("\"{}\":null", JSON_MARKER), "%s"),
+            json_path: path,
+            ..config.clone()
+        });
+    }
+
+    configs
+}
+
+//collects json pointers and dot paths like user.items[0] of every object
+fn find_json_objects(value: &serde_json::Value, pointer: &str, path: &str, objects: &mut Vec<(String, String)>) {
+    match value {
+        serde_json::Value::Object(object) => {
+            objects.push((pointer.to_string(), path.to_string()));
+
+            for (k, v) in object.iter().filter(|(k, _)| k.as_str() != JSON_MARKER) {
+                let path = if path.is_empty() { k.to_owned() } else { format!("{}.{}", path, k) };
+                find_json_objects(v, &format!("{}/{}", pointer, k.replace('~', "~0").replace('/', "~1")), &path, objects);
+            }
+        },
+        serde_json::Value::Array(array) => {
+            for (i, v) in array.iter().enumerate() {
+                find_json_objects(v, &format!("{}/{}", pointer, i), &format!("{}[{}]", path, i), objects);
+            }
+        },
+        _ => ()
+    }
+}
+
+fn adjust_xml_body(body: &str) -> String {
+    lazy_static! {
+        //closing tag right after a text, i.e the end of the leaf element
+        static ref RE_LEAF_END: Regex = Regex::new(r"[^>\s]\s*</[^>]+>").unwrap();
+        //the first element after the xml declaration, doctype and comments
+        static ref RE_ROOT_START: Regex = Regex::new(r"<[^?!]").unwrap();
+    }
+
+    if body.trim().is_empty() {
+        return String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<root>%s</root>")
+    }
+
+    //<soap:Body><m:Op><a>1</a></m:Op> -> <soap:Body><m:Op><a>1</a>%s</m:Op>
+    let position = match RE_LEAF_END.find_iter(body).last() {
+        //the root is a leaf itself, so it is wrapped to keep the text and parameters apart: <a>1</a> -> <root><a>1</a>%s</root>
+        Some(val) if !body[val.end()..].contains("</") => {
+            let start = RE_ROOT_START.find(body).map_or(0, |x| x.start());
+            return [&body[..start], "<root>", &body[start..val.end()], "%s</root>", &body[val.end()..]].concat()
+        },
+        Some(val) => body[val.end()..].find("</").map(|x| x + val.end()).or_else(|| body.rfind("</")),
+        None => body.rfind("</"),
+    };
+
+    match position {
+        Some(val) => [&body[..val], "%s", &body[val..]].concat(),
+        None => body.to_string() + "%s",
+    }
+}
+
+//takes the boundary from the Content-Type header or sets the header with a new one
+pub fn set_multipart_content_type(headers: &mut HashMap<String, String>) -> String {
+    let content_type = headers
+        .iter()
+        .find(|(k, v)| k.eq_ignore_ascii_case("content-type") && v.contains("multipart/form-data"))
+        .map(|(k, v)| (k.to_owned(), v.to_owned()));
+
+    let boundary = content_type.as_ref().and_then(|(_, v)| {
+        v.split(';')
+            .find_map(|x| x.trim().strip_prefix("boundary="))
+            .map(|x| x.trim_matches('"').to_string())
+    });
+
+    //there can be other content types from the default headers
+    headers.retain(|k, _| !k.eq_ignore_ascii_case("content-type"));
+
+    match (content_type, boundary) {
+        (Some((key, value)), Some(boundary)) => {
+            headers.insert(key, value);
+            boundary
+        },
+        _ => {
+            let boundary = String::from("----x8") + &random_line(16);
+            headers.insert(
+                String::from("Content-Type"),
+                format!("multipart/form-data; boundary={}", boundary)
+            );
+            boundary
+        }
+    }
+}
+
+//a separate part for every parameter
+pub fn multipart_parameter_template(boundary: &str) -> String {
+    format!("--{}\r\nContent-Disposition: form-data; name=\"%k\"\r\n\r\n%v\r\n", boundary)
+}
+
+//adds an injection point before the closing boundary, so the existing parts (including files) stay untouched
+pub fn adjust_multipart_body(body: &str, boundary: &str) -> String {
+    let closing = format!("--{}--", boundary);
+
+    match body.rfind(&closing) {
+        Some(val) => [&body[..val], "%s", &body[val..]].concat(),
+        None => [body, "%s", &closing, "\r\n"].concat(),
+    }
+}
+
+//<%k>%v</%k> for elements and %k="%v" when the injection point is within a tag
+pub fn xml_parameter_template(body: &str) -> String {
+    let before = &body[..body.find("%s").unwrap_or(0)];
+
+    match (before.rfind('<'), before.rfind('>')) {
+        (Some(open), Some(close)) if open > close => String::from(" %k=\"%v\""),
+        (Some(_), None) => String::from(" %k=\"%v\""),
+        _ => String::from("<%k>%v</%k>"),
+    }
+}
+
+//element and attribute names can't start with digits and contain most of the special chars
+pub fn is_xml_name(name: &str) -> bool {
+    lazy_static! {
+        static ref RE: Regex = Regex::new(r"^[A-Za-z_][A-Za-z0-9_.\-]*$").unwrap();
+    }
+
+    //parameters with custom values look like name=value
+    RE.is_match(name.split('=').next().unwrap_or(""))
+}
+
+//framework specific presets for nested keys
+pub const PRESETS: [&str; 5] = ["php-array", "php-object", "rails", "spring", "aspnet"];
+
+//spring and asp.net bind obj.attr, php and rails use obj[attr]
+pub fn is_dotted_preset(preset: &str) -> bool {
+    matches!(preset, "spring" | "aspnet")
+}
+
+//parents of nested keys like user[name] or user.name within the request or the page
+pub fn find_parents(text: &str, dotted: bool) -> Vec<String> {
+    lazy_static! {
+        static ref RE_BRACKETS: Regex = Regex::new(r#"(?m)(?:name=["']?|[?&]|^)([A-Za-z_][\w-]*)(?:\[|%5[Bb])\w"#).unwrap();
+        static ref RE_DOTTED: Regex = Regex::new(r#"(?m)(?:name=["']?|[?&]|^)([A-Za-z_][\w-]*)\.[A-Za-z_][\w-]*(?:=|["'])"#).unwrap();
+    }
+
+    let re = if dotted { &*RE_DOTTED } else { &*RE_BRACKETS };
+
+    let mut parents: Vec<String> = Vec::new();
+    for cap in re.captures_iter(text) {
+        if !parents.contains(&cap[1].to_string()) {
+            parents.push(cap[1].to_string());
+        }
+    }
+    parents
+}
+
+//turns words into the nested keys of the preset:
+//php-array: word[], php-object and rails: parent[word], spring and aspnet: parent.word
+pub fn preset_params(preset: &str, parents: &[String], params: &[String]) -> Vec<String> {
+    if preset == "php-array" {
+        return params.iter().map(|x| format!("{}[]", x)).collect()
+    }
+
+    let mut nested: Vec<String> = Vec::with_capacity(parents.len() * params.len());
+    for parent in parents {
+        for param in params {
+            if is_dotted_preset(preset) {
+                nested.push(format!("{}.{}", parent, param));
+            } else {
+                nested.push(format!("{}[{}]", parent, param));
+            }
+        }
+    }
+    nested
+}
+
+//the string that replaces %s within the url, the body or the header value
+pub fn make_injection(config: &Config, query: &[(String, String)]) -> String {
+    if query.is_empty() {
+        return String::new()
+    }
+
+    match config.location() {
+        Location::Body => make_body(config, query),
+        Location::HeaderValue | Location::Cookies => make_header_value(config, query),
+        Location::Headers => String::new(),
+        Location::Path => make_path(config, query),
+        Location::Query => make_query(config, query),
+    }
+}
+
+//removes the injection point that parse_url adds to the query string
+pub fn remove_query_injection(url: &str) -> String {
+    ["?%s", "&%s", "%3f%s", "%26%s"].iter().fold(url.to_string(), |url, x| url.replace(x, ""))
+}
+
+//a copy of the config that sends parameters to the location.
+//The config itself has to be made for the query
+pub fn location_config(config: &Config, location: Location) -> Config {
+    let mut config = Config {
+        locations: Vec::new(),
+        ..config.clone()
+    };
+
+    if location == Location::Query {
+        return config
+    }
+
+    config.url = remove_query_injection(&config.url);
+    config.path = remove_query_injection(&config.path);
+
+    match location {
+        Location::Body => {
+            let content_type = config.headers
+                .iter()
+                .find(|(k, _)| k.eq_ignore_ascii_case("content-type"))
+                .map(|(_, v)| v.to_owned())
+                .unwrap_or_default();
+
+            //the body type is detected once again, because only bodies are checked for it
+            if config.body_type.contains('-') {
+                config.body_type = if content_type.contains("json") || config.body.starts_with('{') {
+                    String::from("json-")
+                } else if content_type.contains("xml") || config.body.trim_start().starts_with('<') {
+                    String::from("xml-")
+                } else {
+                    String::from("urlencode-")
+                };
+            }
+
+            if !config.body.contains("%s") {
+                config.body = adjust_body(&config.body, &config.body_type);
+            }
+
+            config.parameter_template = if config.body_type.contains("json") {
+                String::from("\"%k\":\"%v\", ")
+            } else if config.body_type.contains("xml") {
+                xml_parameter_template(&config.body)
+            } else {
+                String::from("%k=%v&")
+            };
+
+            if content_type.is_empty() {
+                config.headers.insert(
+                    String::from("Content-Type"),
+                    String::from(if config.body_type.contains("json") {
+                        "application/json"
+                    } else if config.body_type.contains("xml") {
+                        "application/xml"
+                    } else {
+                        "application/x-www-form-urlencoded"
+                    })
+                );
+            }
+
+            config.as_body = true;
+        },
+        Location::Cookies => {
+            add_cookie_injection(&mut config.headers);
+            config.parameter_template = String::from("%k=%v; ");
+            config.within_headers = true;
+            config.cookies_discovery = true;
+        },
+        //a lot of headers within a request can be rejected
+        Location::Headers => {
+            config.max = config.max.min(64);
+            config.headers_discovery = true;
+        },
+        _ => ()
+    }
+
+    config
+}
+
+pub fn make_header_value(config: &Config, query: &[(String, String)]) -> String {
     make_query(config, query)
 }
 
-pub fn make_body(config: &Config, query: &HashMap<String, String>) -> String {
+pub fn make_body(config: &Config, query: &[(String, String)]) -> String {
     let mut body: String = String::new();
 
     for (k, v) in query {
-        if config.body_type.contains("json") && RE_JSON_WORDS_WITHOUT_QUOTES.is_match(v) {
+        if config.body_type.contains("multipart") {
+            //quotes would break the Content-Disposition header
+            body.push_str(&config.parameter_template.replace("%k", &k.replace('"', "%22")).replace("%v", v));
+        } else if config.body_type.contains("xml") {
+            body.push_str(&config.parameter_template.replace("%k", k).replace("%v", &escape_xml(v)));
+        } else if config.body_type.contains("json") && RE_JSON_WORDS_WITHOUT_QUOTES.is_match(v) {
             body.push_str(&config.parameter_template.replace("%k", k).replace("\"%v\"", v));
         } else {
             body.push_str(&config.parameter_template.replace("%k", k).replace("%v", v));
@@ -265,7 +700,7 @@ pub fn make_body(config: &Config, query: &HashMap<String, String>) -> String {
     body
 }
 
-pub fn make_query(config: &Config, params: &HashMap<String, String>) -> String {
+pub fn make_query(config: &Config, params: &[(String, String)]) -> String {
     let mut query: String = String::new();
 
     for (k, v) in params {
@@ -280,6 +715,46 @@ pub fn make_query(config: &Config, params: &HashMap<String, String>) -> String {
     }
 }
 
+//;k=v or /k/v with every key and value encoded separately
+pub fn make_path(config: &Config, params: &[(String, String)]) -> String {
+    let mut path: String = String::new();
+
+    for (k, v) in params {
+        path.push_str(
+            &config.parameter_template
+                .replace("%k", &utf8_percent_encode(k, &PATH_SEGMENT).to_string())
+                .replace("%v", &utf8_percent_encode(v, &PATH_SEGMENT).to_string())
+        );
+    }
+
+    path
+}
+
+//puts %s at the end of the path, before the trailing slash and the query string
+pub fn add_path_injection(target: &str, mode: &str) -> String {
+    if target.contains("%s") {
+        return target.to_string()
+    }
+
+    let start = match target.find("://") {
+        Some(val) => target[val + 3..].find(['/', '?', '#']).map_or(target.len(), |x| x + val + 3),
+        None => 0
+    };
+    let end = target[start..].find(['?', '#']).map_or(target.len(), |x| x + start);
+    let path = &target[start..end];
+
+    //segments start with a slash themselves
+    let position = if path.ends_with('/') && (mode == "segments" || path.len() > 1) {
+        end - 1
+    } else {
+        end
+    };
+
+    let injection = if path.is_empty() && mode == "matrix" { "/%s" } else { "%s" };
+
+    [&target[..position], injection, &target[position..]].concat()
+}
+
 //"param" -> param:random_value
 //"param=value" -> param:value
 pub fn make_hashmap(
@@ -302,28 +777,67 @@ pub fn make_hashmap(
     hashmap
 }
 
-pub fn parse_request(config: Config, proto: &str, request: &str, custom_parameter_template: bool) -> Option<Config> {
+//fills url related fields and adds an injection point to the url if needed
+pub fn parse_url(config: Config, target: &str) -> Result<Config, X8Error> {
+    let url = Url::parse(target).map_err(|err| X8Error::InvalidUrl(target.to_string(), err))?;
+
+    let host = url.host_str().unwrap_or("").to_string();
+    let mut path = url[url::Position::BeforePath..].to_string();
+    let mut url = url.to_string();
+
+    if !config.path_injection.is_empty() {
+        url = add_path_injection(&url, &config.path_injection);
+        path = add_path_injection(&path, &config.path_injection);
+    } else if !config.as_body && !config.within_headers && !config.headers_discovery && url.contains('?') && url.contains('=') && !url.contains("%s") {
+        if config.encode {
+            url.push_str("%26%s");
+            path.push_str("%26%s");
+        } else {
+            url.push_str("&%s");
+            path.push_str("&%s");
+        }
+    } else if !config.as_body && !config.within_headers && !config.headers_discovery && !url.contains("%s") {
+        if config.encode {
+            url.push_str("%3f%s");
+            path.push_str("%3f%s");
+        } else {
+            url.push_str("?%s");
+            path.push_str("?%s");
+        }
+    }
+
+    Ok(Config {
+        initial_url: target.to_string(),
+        url,
+        host,
+        path,
+        ..config
+    })
+}
+
+pub fn parse_request(config: Config, proto: &str, request: &str, custom_parameter_template: bool) -> Result<Config, X8Error> {
     let mut lines = request.lines();
     let mut host = String::new();
     let mut content_type = String::new();
     let mut headers: HashMap<String, String> = config.headers.clone();
     let mut within_headers: bool = config.within_headers;
-    let mut firstline = lines.next()?.split(' ');
-    let method = firstline.next()?.to_string();
-    let mut path = firstline.next()?.to_string();
+    let mut has_cookies = false;
+    let mut firstline = lines.next().ok_or(X8Error::RequestFileParse)?.split(' ');
+    let method = firstline.next().ok_or(X8Error::RequestFileParse)?.to_string();
+    let mut path = firstline.next().ok_or(X8Error::RequestFileParse)?.to_string();
 
-    let http2: bool = firstline.next()?.to_string().contains("HTTP/2");
+    let http2: bool = firstline.next().ok_or(X8Error::RequestFileParse)?.to_string().contains("HTTP/2");
 
     //read headers
-    while let Some(line) = lines.next() {
+    for line in lines.by_ref() {
         if line.is_empty() {
             break;
         }
 
         let mut k_v = line.split(':');
-        let key = k_v.next()?;
+        let key = k_v.next().ok_or(X8Error::RequestFileParse)?;
         let value: String = [
-            k_v.next()?.trim().to_owned(),
+            k_v.next().ok_or(X8Error::RequestFileParse)?.trim().to_owned(),
             k_v.map(|x| ":".to_owned() + x).collect(),
         ].concat();
 
@@ -340,12 +854,25 @@ pub fn parse_request(config: Config, proto: &str, request: &str, custom_paramete
                 }
             },
             "content-length" => continue,
+            //several cookie headers are sent as a single one
+            "cookie" if has_cookies => {
+                if let Some(cookies) = headers.iter_mut().find(|(k, _)| k.eq_ignore_ascii_case("cookie")).map(|(_, v)| v) {
+                    cookies.push_str("; ");
+                    cookies.push_str(&value);
+                    continue
+                }
+            },
+            "cookie" => has_cookies = true,
             _ => ()
         };
 
         headers.insert(key.to_string(), value);
     }
 
+    if config.cookies_discovery {
+        add_cookie_injection(&mut headers);
+    }
+
     let mut parameter_template = if !custom_parameter_template {
         if config.within_headers {
             String::from("%k=%v; ")
@@ -359,37 +886,74 @@ pub fn parse_request(config: Config, proto: &str, request: &str, custom_paramete
         config.parameter_template.clone()
     };
 
-    let mut body = lines.next().unwrap_or("").to_string();
-    while let Some(part) = lines.next() {
-        if !part.is_empty() {
-            body.push_str("\r\n");
-            body.push_str(part);
+    //empty lines separate headers and values of multipart parts, so they are kept
+    let body = if content_type.contains("multipart/form-data") || config.body_type.starts_with("multipart") {
+        lines.collect::<Vec<&str>>().join("\r\n")
+    } else {
+        let mut body = lines.next().unwrap_or("").to_string();
+        for part in lines {
+            if !part.is_empty() {
+                body.push_str("\r\n");
+                body.push_str(part);
+            }
         }
-    }
+        body
+    };
 
-    //check whether the body type can be json
+    //check whether the body type can be json, xml or multipart
      //TODO check whether can be combined with the same check in args.rs
     let body_type = if config.body_type.contains('-') && config.as_body && !custom_parameter_template
+    && content_type.contains("multipart/form-data") {
+        String::from("multipart-")
+    } else if config.body_type.contains('-') && config.as_body && !custom_parameter_template
     && (
         content_type.contains("json") || (!body.is_empty() && body.starts_with('{') )
     ) {
         parameter_template = String::from("\"%k\":\"%v\", ");
         String::from("json-")
+    } else if config.body_type.contains('-') && config.as_body && !custom_parameter_template
+    && (
+        content_type.contains("xml") || body.trim_start().starts_with('<')
+    ) {
+        String::from("xml-")
     } else {
         config.body_type
     };
 
     //if --as-body is specified and body is empty or lacks injection points - add an injection point
-    let body = if config.as_body && ((!body.is_empty() && !body.contains("%s")) || body.is_empty()) {
+    let body = if config.as_body && !body_type.contains("multipart") && ((!body.is_empty() && !body.contains("%s")) || body.is_empty()) {
         adjust_body(&body, &body_type)
     } else {
         body
     };
 
+    if body_type.contains("xml") && config.as_body && !custom_parameter_template {
+        parameter_template = xml_parameter_template(&body);
+    }
+
+    let body = if body_type.contains("multipart") && config.as_body {
+        let boundary = set_multipart_content_type(&mut headers);
+
+        if !custom_parameter_template {
+            parameter_template = multipart_parameter_template(&boundary);
+        }
+
+        if body.contains("%s") {
+            body
+        } else {
+            adjust_multipart_body(&body, &boundary)
+        }
+    } else {
+        body
+    };
+
     let mut url = [proto,"://", &host, &path].concat();
     let initial_url = url.clone();
 
-    if !config.as_body && url.contains('?') && !within_headers && !config.headers_discovery && url.contains('=') && !url.contains("%s") {
+    if !config.path_injection.is_empty() {
+        url = add_path_injection(&url, &config.path_injection);
+        path = add_path_injection(&path, &config.path_injection);
+    } else if !config.as_body && url.contains('?') && !within_headers && !config.headers_discovery && url.contains('=') && !url.contains("%s") {
         url.push_str("&%s");
         path.push_str("&%s");
     } else if !config.as_body && !within_headers && !config.headers_discovery {
@@ -397,7 +961,7 @@ pub fn parse_request(config: Config, proto: &str, request: &str, custom_paramete
         path.push_str("?%s");
     }
 
-    Some(Config {
+    Ok(Config {
         method,
         url,
         host,
@@ -412,10 +976,12 @@ pub fn parse_request(config: Config, proto: &str, request: &str, custom_paramete
     })
 }
 
+//the first char is always a letter, so random lines are valid xml names as well
 pub fn random_line(size: usize) -> String {
     (0..size)
-        .map(|_| {
-            let idx = rand::thread_rng().gen_range(0,RANDOM_CHARSET.len());
+        .map(|i| {
+            //the first 26 chars of the charset are letters
+            let idx = rand::thread_rng().gen_range(0, if i == 0 { 26 } else { RANDOM_CHARSET.len() });
             RANDOM_CHARSET[idx] as char
         })
         .collect()
@@ -429,7 +995,28 @@ where
     Ok(io::BufReader::new(file).lines())
 }
 
-pub fn create_output(config: &Config, stats: &Statistic, found_params: HashMap<String, String>) -> String {
+#[derive(Serialize)]
+struct JsonOutput<'a> {
+    method: &'a str,
+    url: &'a str,
+    parameters: Vec<JsonParameter<'a>>,
+    //found parameters are missing from the spec, these ones are in it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    documented_parameters: Option<&'a Vec<String>>,
+    amount_of_requests: usize,
+}
+
+#[derive(Serialize)]
+struct JsonParameter<'a> {
+    #[serde(flatten)]
+    finding: &'a Finding,
+    //human readable reason like "Changes page: 132 -> 140"
+    description: String,
+}
+
+pub fn create_output(config: &Config, result: &ScanResult) -> String {
+    let found_params = &result.found_params;
+
     match config.output_format.as_str() {
         "url" => {
             let mut line = if !found_params.is_empty() {
@@ -443,8 +1030,8 @@ pub fn create_output(config: &Config, stats: &Statistic, found_params: HashMap<S
 
             if !found_params.is_empty() {
 
-                for (param, _) in &found_params {
-                    line.push_str(&param);
+                for param in found_params.keys() {
+                    line.push_str(param);
                     if !param.contains('=') {
                         line.push('=');
                         line.push_str(&random_line(config.value_size));
@@ -460,43 +1047,57 @@ pub fn create_output(config: &Config, stats: &Statistic, found_params: HashMap<S
             line
         }
         "json" => {
-            let mut line = format!(
-                "{{\"method\":\"{}\", \"url\":\"{}\", \"parameters\":[",
-                &config.method,
-                &config.initial_url
-            );
-
-            if !found_params.is_empty() {
-
-                for (param, reason) in &found_params {
-                    line.push_str(&format!("{{\"name\":\"{}\", \"reason\":\"{}\"}}, ", param, reason));
-                }
-
-                line = line[..line.len() - 2].to_string();
-            }
-
-            line.push_str(&format!("], \"amount_of_requests\":{}}}\n", stats.amount_of_requests));
+            let output = JsonOutput {
+                method: &config.method,
+                url: &config.initial_url,
+                parameters: found_params.values().map(|finding| JsonParameter {
+                    description: finding.to_string(),
+                    finding,
+                }).collect(),
+                documented_parameters: config.documented_parameters.as_ref(),
+                amount_of_requests: result.amount_of_requests,
+            };
 
-            line
+            //serializing of these structs can't fail
+            serde_json::to_string(&output).unwrap() + "\n"
         },
         "request" => {
-            generate_request(config, &make_hashmap(&found_params.keys().map(|x| x.to_owned()).collect::<Vec<String>>(), config.value_size))
-                .lines()
-                .skip(1)
-                .collect::<Vec<&str>>()
-                .join("\n") + "\n"
+            generate_http_request(config, &make_hashmap(&found_params.keys().map(|x| x.to_owned()).collect::<Vec<String>>(), config.value_size))
         },
         _ => {
             let mut line = format!("{} {} % ", &config.method, &config.initial_url);
 
             if !found_params.is_empty() {
 
-                for (param, _) in &found_params {
-                    line.push_str(&param);
+                for finding in found_params.values() {
+                    line.push_str(&finding.name);
+
+                    if !config.locations.is_empty() {
+                        line.push_str(&format!(
+                            " ({})",
+                            finding.locations.iter().map(|x| x.to_string()).collect::<Vec<String>>().join(", ")
+                        ));
+                    }
+
+                    if let Some(pollution) = &finding.pollution {
+                        line.push_str(&format!(" [{}]", pollution));
+                    }
+
+                    if !finding.values.is_empty() {
+                        line.push_str(&format!(
+                            " (values: {})",
+                            finding.values.iter().map(|x| x.value.as_str()).collect::<Vec<&str>>().join(", ")
+                        ));
+                    }
+
                     line.push_str(", ")
                 }
 
                 line = line[..line.len() - 2].to_string();
+
+                if config.documented_parameters.is_some() {
+                    line.push_str(" (missing from the spec)");
+                }
             }
 
             line.push('\n');
@@ -505,6 +1106,24 @@ pub fn create_output(config: &Config, stats: &Statistic, found_params: HashMap<S
     }
 }
 
+//escapes special characters for xml and html documents
+pub fn escape_xml(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
 //beautify json before comparing responses
 pub fn beautify_json(json: &str) -> String {
     let json = json.replace("\\\"", "'");
@@ -518,4 +1137,153 @@ pub fn beautify_json(json: &str) -> String {
 //same with html
 pub fn beautify_html(html: &str) -> String {
     html.replace(">", ">\n")
-}
\ No newline at end of file
+}
+
+//and xml
+pub fn beautify_xml(xml: &str) -> String {
+    beautify_html(xml)
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn adjust_xml_body_injection_point() {
+        assert_eq!(adjust_xml_body(""), "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<root>%s</root>");
+        assert_eq!(
+            adjust_xml_body("<soap:Body><m:Op><a>1</a><b>2</b></m:Op></soap:Body>"),
+            "<soap:Body><m:Op><a>1</a><b>2</b>%s</m:Op></soap:Body>"
+        );
+        assert_eq!(adjust_xml_body("<r><a/></r>"), "<r><a/>%s</r>");
+    }
+
+    #[test]
+    fn adjust_xml_body_leaf_root() {
+        assert_eq!(adjust_xml_body("<a>text</a>"), "<root><a>text</a>%s</root>");
+        assert_eq!(
+            adjust_xml_body("<?xml version=\"1.0\"?>\n<!-- c --><a>text</a>\n"),
+            "<?xml version=\"1.0\"?>\n<!-- c --><root><a>text</a>%s</root>\n"
+        );
+    }
+
+    #[test]
+    fn xml_templates() {
+        assert_eq!(xml_parameter_template("<r><a>1</a>%s</r>"), "<%k>%v</%k>");
+        assert_eq!(xml_parameter_template("<r a=\"1\"%s></r>"), " %k=\"%v\"");
+    }
+
+    #[test]
+    fn adjust_multipart_body_injection_point() {
+        let body = "--b\r\nContent-Disposition: form-data; name=\"file\"; filename=\"a.txt\"\r\n\r\nx\r\n--b--\r\n";
+
+        assert_eq!(
+            adjust_multipart_body(body, "b"),
+            "--b\r\nContent-Disposition: form-data; name=\"file\"; filename=\"a.txt\"\r\n\r\nx\r\n%s--b--\r\n"
+        );
+        assert_eq!(adjust_multipart_body("", "b"), "%s--b--\r\n");
+        assert_eq!(
+            multipart_parameter_template("b").replace("%k", "debug").replace("%v", "1"),
+            "--b\r\nContent-Disposition: form-data; name=\"debug\"\r\n\r\n1\r\n"
+        );
+    }
+
+    #[test]
+    fn multipart_content_type() {
+        let mut headers = HashMap::from([(String::from("content-type"), String::from("multipart/form-data; boundary=\"abc\""))]);
+        assert_eq!(set_multipart_content_type(&mut headers), "abc");
+        assert_eq!(headers.len(), 1);
+
+        let mut headers = HashMap::from([(String::from("Content-Type"), String::from("application/json"))]);
+        let boundary = set_multipart_content_type(&mut headers);
+        assert!(boundary.starts_with("----x8"));
+        assert_eq!(headers.len(), 1);
+        assert!(headers.values().all(|x| x == &format!("multipart/form-data; boundary={}", boundary)));
+    }
+    #[test]
+    fn find_parents_brackets() {
+        assert_eq!(
+            find_parents("a=1&user[name]=x&user[id]=2&filter%5Bq%5D=3&list[]=4", false),
+            vec!["user", "filter"]
+        );
+        assert_eq!(
+            find_parents(r#"<input name="order[id]"><input name='item[0]'>"#, false),
+            vec!["order", "item"]
+        );
+        //the dotted form isn't a parent for php and rails
+        assert!(find_parents("user.name=x", false).is_empty());
+    }
+
+    #[test]
+    fn find_parents_dotted() {
+        assert_eq!(find_parents("user.name=x&user.id=1&a.b.c=2", true), vec!["user"]);
+        assert_eq!(find_parents(r#"<input name="address.city">"#, true), vec!["address"]);
+        //domains and file names aren't parents
+        assert!(find_parents("see example.com or index.html", true).is_empty());
+    }
+
+    #[test]
+    fn preset_params_nesting() {
+        let parents = vec![String::from("user"), String::from("order")];
+        let params = vec![String::from("id"), String::from("admin")];
+
+        assert_eq!(preset_params("php-array", &parents, &params), vec!["id[]", "admin[]"]);
+        assert_eq!(
+            preset_params("rails", &parents, &params),
+            vec!["user[id]", "user[admin]", "order[id]", "order[admin]"]
+        );
+        assert_eq!(preset_params("spring", &parents[..1], &params), vec!["user.id", "user.admin"]);
+        assert!(preset_params("php-object", &[], &params).is_empty());
+    }
+    #[test]
+    fn add_path_injection_segments() {
+        assert_eq!(add_path_injection("https://example.com/api/users?a=1", "segments"), "https://example.com/api/users%s?a=1");
+        //segments start with a slash, so the trailing one is replaced
+        assert_eq!(add_path_injection("https://example.com/api/", "segments"), "https://example.com/api%s/");
+        assert_eq!(add_path_injection("https://example.com/", "segments"), "https://example.com%s/");
+        assert_eq!(add_path_injection("https://example.com", "segments"), "https://example.com%s");
+        assert_eq!(add_path_injection("/api#top", "segments"), "/api%s#top");
+    }
+
+    #[test]
+    fn add_path_injection_matrix() {
+        assert_eq!(add_path_injection("https://example.com/api/users", "matrix"), "https://example.com/api/users%s");
+        assert_eq!(add_path_injection("https://example.com/api/", "matrix"), "https://example.com/api%s/");
+        //the root path keeps its slash
+        assert_eq!(add_path_injection("https://example.com/", "matrix"), "https://example.com/%s");
+        assert_eq!(add_path_injection("https://example.com?a=1", "matrix"), "https://example.com/%s?a=1");
+        //an existing injection point isn't moved
+        assert_eq!(add_path_injection("https://example.com/%s/users", "matrix"), "https://example.com/%s/users");
+    }
+    fn headers(list: &[(&str, &str)]) -> Vec<(String, String)> {
+        list.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    }
+
+    #[test]
+    fn compare_headers_changes() {
+        let before = headers(&[("content-type", "text/html"), ("location", "/a"), ("x-old", "1")]);
+        let after = headers(&[("content-type", "text/html"), ("location", "/b"), ("x-debug-mode", "on")]);
+
+        assert_eq!(
+            compare_headers(&before, &after),
+            vec!["Changes Location", "Adds header X-Debug-Mode", "Removes header X-Old"]
+        );
+        assert!(compare_headers(&before, &before).is_empty());
+    }
+
+    #[test]
+    fn compare_headers_noisy() {
+        let before = headers(&[("date", "Mon, 01 Jan 2024 00:00:00 GMT"), ("etag", "\"a\""), ("x-request-id", "1")]);
+        let after = headers(&[("date", "Mon, 01 Jan 2024 00:00:01 GMT"), ("content-length", "10"), ("x-runtime", "0.1")]);
+
+        assert!(compare_headers(&before, &after).is_empty());
+    }
+
+    #[test]
+    fn compare_headers_cookies() {
+        let before = headers(&[("set-cookie", "session=1; Path=/"), ("set-cookie", "old=1")]);
+        let after = headers(&[("set-cookie", "session=2; Path=/"), ("set-cookie", "debug=1; HttpOnly")]);
+
+        //values of cookies are ignored
+        assert_eq!(compare_headers(&before, &after), vec!["Sets cookie debug", "Doesn't set cookie old"]);
+    }
+}
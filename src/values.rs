@@ -0,0 +1,155 @@
+use crate::{
+    error::X8Error,
+    reflections::count_reflections,
+    requests::request_pairs,
+    structs::{Config, GraphqlErrors, ResponseData, Statistic, ValueResponse},
+    utils::{compare, random_line},
+};
+use regex::Regex;
+use reqwest::Client;
+use std::collections::{hash_map::Entry, HashMap};
+
+//booleans, small ints, roles, formats and debug levels
+pub const COMMON_VALUES: [&str; 26] = [
+    "true", "false", "1", "0", "-1", "2", "10", "yes", "no", "on", "off", "null",
+    "admin", "user", "guest", "root",
+    "json", "xml", "html", "csv", "txt",
+    "debug", "trace", "verbose", "dev", "test",
+];
+
+//a request with a random value of the same length and the diffs between two of such requests
+struct Baseline {
+    response: ResponseData,
+    noise: Vec<String>,
+}
+
+//sends the found parameter with every value and returns the values that change the response
+//in comparison with random values of the same length
+pub async fn enumerate_values(
+    config: &Config,
+    stats: &mut Statistic,
+    client: &Client,
+    name: &str,
+    reflections: usize,
+) -> Result<Vec<ValueResponse>, X8Error> {
+    let mut baselines: HashMap<usize, Baseline> = HashMap::new();
+    let mut distinct: Vec<ValueResponse> = Vec::new();
+
+    for value in config.enumerate_values.iter() {
+        if let Entry::Vacant(entry) = baselines.entry(value.len()) {
+            let first = random_line(value.len());
+            let second = random_line(value.len());
+
+            let response = request_pairs(config, stats, client, &[(name.to_string(), first.clone())], reflections).await?;
+            //the value is only replaced when it is reflected, otherwise the same chars within the page would be replaced as well
+            let reflected = count_reflections(&response.text, &response.headers, &first) > reflections;
+            let response = without_value(response, &first, reflected);
+
+            let second_response = without_value(
+                request_pairs(config, stats, client, &[(name.to_string(), second.clone())], reflections).await?,
+                &second,
+                reflected
+            );
+
+            let (_, noise) = compare(&response, &second_response)?;
+            entry.insert(Baseline { response, noise });
+        }
+
+        let baseline = &baselines[&value.len()];
+
+        let response = request_pairs(config, stats, client, &[(name.to_string(), value.to_string())], reflections).await?;
+        let value_response = ValueResponse {
+            value: value.to_string(),
+            code: response.code,
+            length: response.text.len(),
+        };
+
+        //the value is put in place of the random one instead of removing it from the response,
+        //because values like "html" can be within the page regardless of the reflection
+        let expected = ResponseData {
+            text: baseline.response.text.replace(PLACEHOLDER, value),
+            code: baseline.response.code,
+            headers: baseline.response.headers.iter().map(|(k, v)| (k.to_string(), v.replace(PLACEHOLDER, value))).collect(),
+            reflected_params: HashMap::new(),
+            graphql_errors: GraphqlErrors::default(),
+            time: response.time,
+        };
+        let (is_code_the_same, diffs) = compare(&expected, &response)?;
+
+        if !is_code_the_same || diffs.iter().any(|x| !baseline.noise.contains(&x.replace(value, PLACEHOLDER))) {
+            distinct.push(value_response);
+        }
+    }
+
+    Ok(distinct)
+}
+
+const PLACEHOLDER: &str = "%x8-value%";
+
+//replaces reflections of the random value, so responses to different values can be compared
+fn without_value(response: ResponseData, value: &str, reflected: bool) -> ResponseData {
+    if !reflected {
+        return response
+    }
+
+    let is_word = |c: Option<char>| c.is_some_and(|c| c.is_alphanumeric() || c == '_');
+
+    //1 shouldn't be replaced within 100
+    let pattern = [
+        if is_word(value.chars().next()) { r"\b" } else { "" },
+        &regex::escape(value),
+        if is_word(value.chars().last()) { r"\b" } else { "" },
+    ].concat();
+
+    let replace = |text: &str| match Regex::new(&format!("(?i){}", pattern)) {
+        Ok(re) => re.replace_all(text, PLACEHOLDER).to_string(),
+        Err(_) => text.replace(value, PLACEHOLDER),
+    };
+
+    ResponseData {
+        text: replace(&response.text),
+        headers: response.headers.iter().map(|(k, v)| (k.to_string(), replace(v))).collect(),
+        ..response
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structs::DefaultResponse;
+
+    fn page(text: &str, headers: &[(&str, &str)]) -> ResponseData {
+        ResponseData {
+            text: text.to_string(),
+            code: 200,
+            headers: headers.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect(),
+            ..ResponseData::default()
+        }
+    }
+
+    #[test]
+    fn without_value_whole_words() {
+        let response = without_value(page("page 1 of 100, id=1", &[("x-page", "1")]), "1", true);
+        assert_eq!(response.text, "page %x8-value% of 100, id=%x8-value%");
+        assert_eq!(response.headers, vec![(String::from("x-page"), String::from("%x8-value%"))]);
+    }
+
+    #[test]
+    fn without_value_case_and_special_chars() {
+        let response = without_value(page("Mode: ADMIN, admins: 2", &[]), "admin", true);
+        assert_eq!(response.text, "Mode: %x8-value%, admins: 2");
+
+        //the boundary is only checked on the sides with word chars
+        let response = without_value(page("a-1 and -1x", &[]), "-1", true);
+        assert_eq!(response.text, "a%x8-value% and -1x");
+
+        let response = without_value(page("q=a.b*c", &[]), "a.b*", true);
+        assert_eq!(response.text, "q=%x8-value%c");
+    }
+
+    #[test]
+    fn without_value_not_reflected() {
+        let response = without_value(page("page 1", &[]), "1", false);
+        assert_eq!(response.text, "page 1");
+    }
+}